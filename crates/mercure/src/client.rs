@@ -1,27 +1,187 @@
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::pin::{pin, Pin};
+use std::str::FromStr;
+use std::task::Poll;
+use std::time::Duration;
 
-use reqwest::header::{self, HeaderMap, HeaderValue};
+use bytes::Bytes;
+use futures_util::future::{self, Either};
+use futures_util::{stream, StreamExt as _, TryStreamExt as _};
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+#[cfg(feature = "serde-errors")]
+use serde::ser::SerializeStruct as _;
 use serde::{Deserialize, Serialize};
-use url::Url;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+#[cfg(feature = "tracing")]
+use tracing::Instrument as _;
+use url::{form_urlencoded, Host, Url};
 
+use crate::cookie::MERCURE_AUTHORIZATION_COOKIE_NAME;
 use crate::jwt::PublisherJwt;
+use crate::time;
 use crate::topic::Topic;
+use crate::topic_selector::TopicSelector;
+use crate::transport::{ReqwestTransport, Transport, TransportRequest};
+use crate::update::{ReconnectPolicy, UpdateStream};
+use crate::HUB_PATH;
 
-/// [The Mercure Protocol, Section 2](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-2)
+/// The maximum number of publish requests sent concurrently by
+/// [`Client::publish_updates`], if not configured.
+const DEFAULT_MAX_CONCURRENT_PUBLISHES: NonZeroUsize = NonZeroUsize::new(16).unwrap();
+
+/// A client for communicating with the Mercure hub, using [`reqwest`].
 ///
-/// > The URL of the hub MUST be the "well-known" [[RFC5785]] fixed path
-/// > "/.well-known/mercure".
+/// See [`GenericClient`] for the methods.
+pub type Client = GenericClient<ReqwestTransport>;
+
+/// A client for communicating with the Mercure hub, using the given
+/// [`Transport`].
 ///
-/// [RFC5785]: https://datatracker.ietf.org/doc/html/rfc5785
-const HUB_PATH: &str = "/.well-known/mercure";
+/// [`Client`] uses [`ReqwestTransport`]. Use [`GenericClient::with_transport`]
+/// or [`ClientBuilder::transport`] for another transport.
+#[derive(Clone)]
+pub struct GenericClient<T = ReqwestTransport> {
+    transport: T,
+    hub_url: HubUrl,
+    publisher_jwt: PublisherJwt,
+    accept_insecure_hub_url: bool,
+    timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    max_concurrent_publishes: Option<NonZeroUsize>,
+    authorization_mode: AuthorizationMode,
+    check_topic_authorization: bool,
+    #[cfg(feature = "tracing")]
+    traceparent: Option<Traceparent>,
+}
 
-/// A client for communicating with the Mercure hub.
-#[derive(Clone, Debug)]
-pub struct Client {
-    http_client: reqwest::Client,
+/// A builder for constructing a [`Client`].
+///
+/// Constructed by [`Client::builder`].
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// # use std::time::Duration;
+/// #
+/// use mercure::client::RetryPolicy;
+/// use mercure::{HubUrl, PublisherJwt, TopicSelector};
+/// use url::Url;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+/// let publisher_jwt = PublisherJwt::new(
+///     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+///     vec![TopicSelector::Wildcard],
+/// )?;
+///
+/// let client = mercure::Client::builder(hub_url, publisher_jwt)
+///     .timeout(Duration::from_secs(10))
+///     .retry_policy(RetryPolicy::new(3, Duration::from_millis(500)))
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "builders do nothing unless `build` is called"]
+pub struct ClientBuilder<T = ReqwestTransport> {
+    transport: T,
     hub_url: HubUrl,
     publisher_jwt: PublisherJwt,
+    accept_insecure_hub_url: bool,
+    timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    max_concurrent_publishes: Option<NonZeroUsize>,
+    authorization_mode: AuthorizationMode,
+    check_topic_authorization: bool,
+    #[cfg(feature = "tracing")]
+    traceparent: Option<Traceparent>,
+}
+
+/// Returns the W3C `traceparent` header value for the current span.
+#[cfg(feature = "tracing")]
+type Traceparent = std::sync::Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
+/// The policy for retrying a publish request after a transient failure.
+///
+/// A publish request is retried only if the connection to the Mercure hub
+/// could not be established, or if the Mercure hub responded with a server
+/// error (5xx) status. In particular, it is not retried after a timeout, as the
+/// update may have been published already.
+///
+/// The delay before each retry starts at the backoff, and is doubled after
+/// each attempt.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+/// How the publisher JWT is presented to the Mercure hub when publishing.
+///
+/// [The Mercure Protocol, Section 6](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-6)
+///
+/// Only one of them is sent at a time.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorizationMode {
+    /// In the `Authorization` header, as a bearer token.
+    #[default]
+    Header,
+    /// In the "mercureAuthorization" cookie.
+    ///
+    /// See [`MERCURE_AUTHORIZATION_COOKIE_NAME`].
+    Cookie,
+}
+
+/// The effective configuration of a [`Client`], excluding the HTTP client and
+/// the publisher JWT.
+///
+/// This can be serialized, e.g. for snapshot testing or auditing, and used to
+/// reconstruct a `Client` with [`Client::from_config`].
+///
+/// A hub URL constructed with [`HubUrl::new_unchecked`] is marked as such when
+/// serialized, so that its path is not checked when deserialized.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(try_from = "UncheckedClientConfig")]
+pub struct ClientConfig {
+    hub_url: HubUrl,
+    #[serde(skip_serializing_if = "is_false")]
+    hub_url_unchecked: bool,
+    accept_insecure_hub_url: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_policy: Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_concurrent_publishes: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "AuthorizationMode::is_header")]
+    authorization_mode: AuthorizationMode,
+    #[serde(default, skip_serializing_if = "is_false")]
+    check_topic_authorization: bool,
+}
+
+/// A [`ClientConfig`] whose hub URL has not been checked yet.
+#[derive(Deserialize)]
+struct UncheckedClientConfig {
+    hub_url: Url,
+    #[serde(default)]
+    hub_url_unchecked: bool,
+    accept_insecure_hub_url: bool,
+    #[serde(default)]
+    timeout: Option<Duration>,
+    #[serde(default)]
+    retry_policy: Option<RetryPolicy>,
+    #[serde(default)]
+    max_concurrent_publishes: Option<NonZeroUsize>,
+    #[serde(default)]
+    authorization_mode: AuthorizationMode,
+    #[serde(default)]
+    check_topic_authorization: bool,
 }
 
 /// The [URL] for connecting to the Mercure hub.
@@ -33,16 +193,91 @@ pub struct Client {
 ///
 /// [RFC5785]: https://datatracker.ietf.org/doc/html/rfc5785
 /// [URL]: https://url.spec.whatwg.org/
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+///
+/// # Serialization
+///
+/// This is serialized as a URL string. Deserializing checks the path as
+/// [`HubUrl::try_from`] does.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
+#[serde(try_from = "Url", into = "Url")]
 pub struct HubUrl(Url);
 
 /// The error type returned when a conversion from [`Url`] to [`HubUrl`] fails.
 #[derive(Debug)]
 pub struct TryFromUrlError;
 
+/// An error which can be returned when parsing a [`HubUrl`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ParseHubUrlError {
+    kind: ParseHubUrlErrorKind,
+    inner: Box<dyn Error + Send + Sync + 'static>,
+}
+
+/// The various types of errors that can cause parsing a [`HubUrl`] to fail.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
+#[non_exhaustive]
+pub enum ParseHubUrlErrorKind {
+    /// The string is not a valid URL.
+    InvalidUrl,
+    /// The path of the URL is not `/.well-known/mercure`.
+    InvalidPath,
+}
+
+/// The error type returned when a conversion from [`String`] to [`RevisionId`]
+/// fails.
+#[derive(Debug)]
+pub struct InvalidRevisionIdError;
+
+/// An error returned from [`HubUrl::require_https`].
+#[derive(Debug)]
+pub struct RequireHttpsError;
+
 /// The privacy setting to use when publishing an update.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize)]
+///
+/// # Serialization
+///
+/// This follows the "private" form field: [`PublishUpdatePrivacy::Private`]
+/// is serialized as `"on"`, and [`PublishUpdatePrivacy::Public`] is
+/// represented by the absence of the field, so it cannot be serialized on its
+/// own. When used as a field, mark it with `#[serde(default)]` and skip
+/// serializing it when public, to round-trip both variants.
+///
+/// Deserializing accepts `"on"` or `true` as [`PublishUpdatePrivacy::Private`],
+/// and `false` or `null` as [`PublishUpdatePrivacy::Public`].
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// #
+/// use mercure::client::PublishUpdatePrivacy;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct PublishIntent {
+///     #[serde(default, skip_serializing_if = "is_public")]
+///     private: PublishUpdatePrivacy,
+/// }
+///
+/// fn is_public(privacy: &PublishUpdatePrivacy) -> bool {
+///     !bool::from(*privacy)
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let intent = PublishIntent {
+///     private: PublishUpdatePrivacy::Public,
+/// };
+/// assert_eq!(serde_json::to_string(&intent)?, "{}");
+/// let intent: PublishIntent = serde_json::from_str(r#"{"private":"on"}"#)?;
+/// assert_eq!(intent.private, PublishUpdatePrivacy::Private);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize)]
 pub enum PublishUpdatePrivacy {
+    #[default]
     #[serde(skip_serializing)]
     Public,
     /// [The Mercure Protocol, Section 5](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-5)
@@ -66,9 +301,160 @@ pub enum PublishUpdatePrivacy {
 ///
 /// [RFC3987]: https://datatracker.ietf.org/doc/html/rfc3987
 /// [RFC4122]: https://datatracker.ietf.org/doc/html/rfc4122
+///
+/// Deserializing checks that the revision ID does not start with `#`, as
+/// `TryFrom<String>` does, so a revision ID generated by the Mercure hub as a
+/// fragment cannot be deserialized.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
+#[serde(try_from = "String")]
 pub struct RevisionId(String);
 
+/// The data of an update, with an indication of its media type.
+///
+/// The Mercure hub passes the data through to subscribers as is, and does not
+/// convey its media type, so subscribers must know it out of band (e.g. from
+/// the topic). This type only saves serializing the data manually.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum UpdateData {
+    /// JSON data, including JSON-LD (`application/ld+json`), serialized in
+    /// compact form.
+    Json(serde_json::Value),
+    /// Plain text data.
+    Text(String),
+    /// Data already serialized in any other format.
+    Raw(String),
+}
+
+/// A builder for publishing an update to the Mercure hub.
+///
+/// Constructed by [`Client::update`].
+#[derive(Debug)]
+#[must_use = "builders do nothing unless `send` is called"]
+pub struct UpdateBuilder<'a, T = ReqwestTransport> {
+    client: &'a GenericClient<T>,
+    params: PublishUpdateParams<'a>,
+    cancel_signal: Option<CancelSignal<'a>>,
+}
+
+/// A builder for subscribing to updates from the Mercure hub.
+///
+/// Constructed by [`Client::subscription`].
+///
+/// # Headers
+///
+/// The subscribe request is sent with the following headers, also when
+/// reconnecting:
+///
+/// - `Accept: text/event-stream`, as required for an event stream.
+/// - `Cache-Control: no-cache`, so that intermediaries do not serve a cached
+///   event stream.
+/// - `Last-Event-ID`, if set with [`SubscriptionBuilder::last_event_id`], or
+///   when reconnecting after an update with an ID has been received.
+///
+/// # Precedence
+///
+/// Extra query parameters are appended after the `topic` parameters, and never
+/// replace them. In particular, a `topic` query parameter adds a topic
+/// selector.
+///
+/// Extra headers replace the headers set by default, i.e. `Accept` and
+/// `Cache-Control`.
+///
+/// Extra query parameters and headers are also sent when reconnecting, so the
+/// `lastEventID` query parameter and the `Last-Event-ID` header cannot be
+/// given as extras, as they would resume from a stale event after
+/// reconnecting: [`SubscriptionBuilder::subscribe`] fails with
+/// [`SubscribeErrorKind::ExtraLastEventId`]. Use
+/// [`SubscriptionBuilder::last_event_id`] instead, which is replaced by the
+/// last event ID received by the returned [`UpdateStream`] when reconnecting.
+///
+/// # HTTP/2
+///
+/// Each subscription keeps a request open to the hub. To multiplex many
+/// subscriptions over a single HTTP/2 connection, share one `reqwest::Client`
+/// between clients. See [`ClientBuilder::http_client`] for how to set up
+/// HTTP/2, including for hubs serving HTTP/2 over cleartext, and how flow
+/// control affects the latency of updates.
+#[derive(Debug)]
+#[must_use = "builders do nothing unless `subscribe` is called"]
+pub struct SubscriptionBuilder<'a, T = ReqwestTransport> {
+    client: &'a GenericClient<T>,
+    topic_selectors: Vec<TopicSelector>,
+    last_event_id: Option<&'a str>,
+    query_params: Vec<(&'a str, &'a str)>,
+    headers: HeaderMap,
+    #[cfg(feature = "compression")]
+    compression: bool,
+    reconnect_policy: ReconnectPolicy,
+    connect_timeout: Option<Duration>,
+    cancel_signal: Option<CancelSignal<'static>>,
+}
+
+/// A future which resolves when an operation should be cancelled.
+///
+/// See [`UpdateBuilder::cancel_on`] and [`SubscriptionBuilder::cancel_on`].
+pub(crate) struct CancelSignal<'a>(Pin<Box<dyn Future<Output = ()> + Send + 'a>>);
+
+impl fmt::Debug for CancelSignal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CancelSignal(..)")
+    }
+}
+
+impl Future for CancelSignal<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+/// A publish request to a fixed topic, with the parameters other than the data
+/// serialized ahead of time.
+///
+/// Constructed by [`Client::prepare_publish`].
+#[derive(Clone, Debug)]
+pub struct PreparedPublish<'a, T = ReqwestTransport> {
+    client: &'a GenericClient<T>,
+    params: String,
+    #[cfg(feature = "tracing")]
+    canonical_url: Url,
+    #[cfg(feature = "tracing")]
+    privacy: PublishUpdatePrivacy,
+}
+
+/// A cheap-to-clone handle for publishing updates through a background task.
+///
+/// Constructed by [`Client::spawn_publisher`]. The background task owns the
+/// client and sends the queued updates to the Mercure hub, up to the limit set
+/// by [`ClientBuilder::max_concurrent_publishes`] at a time, so that many
+/// producer tasks can share a single client without coordinating with each
+/// other.
+#[derive(Clone, Debug)]
+pub struct Publisher {
+    sender: mpsc::Sender<QueuedUpdate>,
+}
+
+/// The background task spawned by [`Client::spawn_publisher`].
+///
+/// Dropping it detaches the background task, which keeps running until all
+/// [`Publisher`] handles are dropped.
+#[derive(Debug)]
+#[must_use = "dropping the task detaches it without waiting for pending updates"]
+pub struct PublisherTask {
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Debug)]
+struct QueuedUpdate {
+    topic: Topic,
+    data: Option<String>,
+    privacy: PublishUpdatePrivacy,
+    result: oneshot::Sender<Result<RevisionId, PublishUpdateError>>,
+}
+
 /// An error returned from [`Client::publish_update`].
 #[derive(Debug)]
 #[non_exhaustive]
@@ -80,24 +466,106 @@ pub struct PublishUpdateError {
 /// The various types of errors that can cause [`Client::publish_update`] to
 /// fail.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
 #[non_exhaustive]
 pub enum PublishUpdateErrorKind {
+    /// The publisher JWT does not authorize publishing to the topic.
+    Unauthorized,
+    /// Refused to send the publisher JWT to a Mercure hub over plaintext HTTP.
+    ///
+    /// See [`Client::danger_accept_insecure_hub_url`].
+    InsecureHubUrl,
     /// Failed to serialize parameters to application/x-www-form-urlencoded.
     SerializeParameters,
     /// Failed to send publish request to Mercure hub.
     SendRequest,
     /// Failed to read publish response from Mercure hub.
     ReadResponse,
+    /// Mercure hub responded with a non-success status, e.g. 401 if the
+    /// publisher JWT is invalid, or 403 if it does not authorize publishing to
+    /// the topic.
+    UnexpectedStatus {
+        /// The status of the response.
+        #[cfg_attr(feature = "serde-errors", serde(serialize_with = "serialize_status"))]
+        status: reqwest::StatusCode,
+        /// The body of the response.
+        body: String,
+    },
+    /// The background task of the [`Publisher`] has been shut down, or has
+    /// stopped before publishing the update.
+    PublisherShutDown,
+    /// Publishing was cancelled by the signal given to
+    /// [`UpdateBuilder::cancel_on`].
+    Cancelled,
+}
+
+/// An error returned from [`Client::subscribe`] or
+/// [`SubscriptionBuilder::subscribe`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SubscribeError {
+    kind: SubscribeErrorKind,
+    inner: Box<dyn Error + Send + Sync + 'static>,
+}
+
+/// The various types of errors that can cause [`Client::subscribe`] to fail.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
+#[non_exhaustive]
+pub enum SubscribeErrorKind {
+    /// The last event ID is not a valid header value.
+    InvalidLastEventId,
+    /// The last event ID was given as an extra `lastEventID` query parameter
+    /// or `Last-Event-ID` header, instead of with
+    /// [`SubscriptionBuilder::last_event_id`].
+    ExtraLastEventId,
+    /// Failed to send subscribe request to Mercure hub.
+    SendRequest,
+    /// Timed out connecting to Mercure hub, see
+    /// [`SubscriptionBuilder::connect_timeout`].
+    ConnectTimeout,
+    /// Mercure hub responded with an error status.
+    ErrorStatus,
+    /// Subscribing was cancelled by the signal given to
+    /// [`SubscriptionBuilder::cancel_on`].
+    Cancelled,
 }
 
 #[derive(Debug, Serialize)]
 struct PublishUpdateParams<'a> {
-    topic: Topic,
+    topic: Cow<'a, Topic>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<&'a str>,
+    data: Option<Cow<'a, str>>,
     #[serde(rename = "private")]
     #[serde(skip_serializing_if = "PublishUpdatePrivacy::is_public")]
     privacy: PublishUpdatePrivacy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<RevisionId>,
+    #[serde(rename = "type")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    r#type: Option<&'a str>,
+    #[serde(serialize_with = "serialize_retry")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<Duration>,
+}
+
+/// Serializes the status as an integer status code.
+#[cfg(feature = "serde-errors")]
+fn serialize_status<S>(status: &reqwest::StatusCode, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    status.as_u16().serialize(serializer)
+}
+
+/// Serializes the reconnection time as an integer number of milliseconds.
+fn serialize_retry<S>(retry: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    retry
+        .map(|retry| u64::try_from(retry.as_millis()).unwrap_or(u64::MAX))
+        .serialize(serializer)
 }
 
 impl Client {
@@ -124,17 +592,150 @@ impl Client {
     /// # }
     /// ```
     pub fn new(http_client: reqwest::Client, hub_url: HubUrl, publisher_jwt: PublisherJwt) -> Self {
+        Self::with_transport(ReqwestTransport::new(http_client), hub_url, publisher_jwt)
+    }
+
+    /// Returns a [`ClientBuilder`] for configuring a `Client`.
+    ///
+    /// See [`ClientBuilder`] for an example.
+    pub fn builder(hub_url: HubUrl, publisher_jwt: PublisherJwt) -> ClientBuilder {
+        ClientBuilder::new(hub_url, publisher_jwt)
+    }
+
+    /// Constructs a new `Client` from a [`ClientConfig`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// use url::Url;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// let client = mercure::Client::new(http_client.clone(), hub_url, publisher_jwt.clone());
+    ///
+    /// let client_config = client.config();
+    /// let client = mercure::Client::from_config(http_client, publisher_jwt, client_config);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_config(
+        http_client: reqwest::Client,
+        publisher_jwt: PublisherJwt,
+        client_config: ClientConfig,
+    ) -> Self {
+        Self::from_config_with_transport(
+            ReqwestTransport::new(http_client),
+            publisher_jwt,
+            client_config,
+        )
+    }
+}
+
+impl<T> GenericClient<T>
+where
+    T: Transport,
+{
+    /// Constructs a new `GenericClient` with the given [`Transport`].
+    pub fn with_transport(transport: T, hub_url: HubUrl, publisher_jwt: PublisherJwt) -> Self {
         Self {
-            http_client,
+            transport,
             hub_url,
             publisher_jwt,
+            accept_insecure_hub_url: false,
+            timeout: None,
+            retry_policy: None,
+            max_concurrent_publishes: None,
+            authorization_mode: AuthorizationMode::Header,
+            check_topic_authorization: false,
+            #[cfg(feature = "tracing")]
+            traceparent: None,
+        }
+    }
+
+    /// Constructs a new `GenericClient` with the given [`Transport`] from a
+    /// [`ClientConfig`].
+    pub fn from_config_with_transport(
+        transport: T,
+        publisher_jwt: PublisherJwt,
+        client_config: ClientConfig,
+    ) -> Self {
+        Self {
+            transport,
+            hub_url: client_config.hub_url,
+            publisher_jwt,
+            accept_insecure_hub_url: client_config.accept_insecure_hub_url,
+            timeout: client_config.timeout,
+            retry_policy: client_config.retry_policy,
+            max_concurrent_publishes: client_config.max_concurrent_publishes,
+            authorization_mode: client_config.authorization_mode,
+            check_topic_authorization: client_config.check_topic_authorization,
+            #[cfg(feature = "tracing")]
+            traceparent: None,
         }
     }
 
+    /// Returns the effective configuration of this `Client`.
+    pub fn config(&self) -> ClientConfig {
+        ClientConfig {
+            hub_url: self.hub_url.clone(),
+            hub_url_unchecked: !self.hub_url.has_well_known_path(),
+            accept_insecure_hub_url: self.accept_insecure_hub_url,
+            timeout: self.timeout,
+            retry_policy: self.retry_policy,
+            max_concurrent_publishes: self.max_concurrent_publishes,
+            authorization_mode: self.authorization_mode,
+            check_topic_authorization: self.check_topic_authorization,
+        }
+    }
+
+    /// Controls whether to send the publisher JWT to a Mercure hub over
+    /// plaintext HTTP.
+    ///
+    /// By default, publishing to a hub URL which does not use HTTPS fails with
+    /// [`PublishUpdateErrorKind::InsecureHubUrl`], unless its host is a
+    /// loopback address (e.g. `localhost`), because the bearer token could
+    /// otherwise be intercepted.
+    ///
+    /// # Warning
+    ///
+    /// You should think very carefully before using this method. Anyone able
+    /// to observe the network traffic can obtain the publisher JWT and use it
+    /// to publish updates.
+    pub fn danger_accept_insecure_hub_url(mut self, accept_insecure_hub_url: bool) -> Self {
+        self.accept_insecure_hub_url = accept_insecure_hub_url;
+        self
+    }
+
     /// Publishes an update to the Mercure hub.
     ///
+    /// The topic can be passed by value or by reference. Passing `&Topic`
+    /// allows reusing it for publishing more updates without cloning it.
+    ///
     /// [The Mercure Protocol, Section 5](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-5)
     ///
+    /// # Revision ID
+    ///
+    /// The returned [`RevisionId`] is the one generated or accepted by the
+    /// Mercure hub. It is read from the `Location` header of the response if
+    /// present, as some Mercure hubs send it there, and otherwise from the
+    /// response body, as specified by the protocol.
+    ///
+    /// # Cancellation
+    ///
+    /// Dropping the returned future aborts the in-flight request. To cancel
+    /// publishing on a signal, e.g. on graceful shutdown, use
+    /// [`UpdateBuilder::cancel_on`], which fails with
+    /// [`PublishUpdateErrorKind::Cancelled`] instead.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -161,188 +762,3593 @@ impl Client {
     /// let privacy = PublishUpdatePrivacy::Public;
     ///
     /// client.publish_update(topic, Some(&data), privacy).await?;
+    ///
+    /// let topic = Topic::new("https://example.com/stocks/ACME".parse()?, vec![]);
+    ///
+    /// for price in ["42.00", "42.10", "41.95"] {
+    ///     client
+    ///         .publish_update(&topic, Some(price), PublishUpdatePrivacy::Public)
+    ///         .await?;
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn publish_update(
-        &self,
-        topic: Topic,
+    pub async fn publish_update<'a>(
+        &'a self,
+        topic: impl Into<Cow<'a, Topic>>,
         data: Option<&str>,
         privacy: PublishUpdatePrivacy,
     ) -> Result<RevisionId, PublishUpdateError> {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            header::CONTENT_TYPE,
-            HeaderValue::from_static("application/x-www-form-urlencoded"),
-        );
-        headers.insert(
-            header::AUTHORIZATION,
-            format!("Bearer {jwt}", jwt = self.publisher_jwt)
-                .parse()
-                .expect("`publisher_jwt` should not contain invalid ASCII"),
-        );
-
-        let params = PublishUpdateParams {
-            topic,
-            data,
-            privacy,
-        };
-
-        let res = self
-            .http_client
-            .post(self.hub_url.0.clone())
-            .headers(headers)
-            .body(
-                serde_html_form::to_string(params).map_err(|err| PublishUpdateError {
-                    kind: PublishUpdateErrorKind::SerializeParameters,
-                    inner: err.into(),
-                })?,
-            )
-            .send()
-            .await
-            .map_err(|err| PublishUpdateError {
-                kind: PublishUpdateErrorKind::SendRequest,
-                inner: err.into(),
-            })?;
-
-        Ok(RevisionId(res.text().await.map_err(|err| {
-            PublishUpdateError {
-                kind: PublishUpdateErrorKind::ReadResponse,
-                inner: err.into(),
-            }
-        })?))
-    }
-}
-
-impl TryFrom<Url> for HubUrl {
-    type Error = TryFromUrlError;
-
-    fn try_from(url: Url) -> Result<Self, Self::Error> {
-        if url.path() != HUB_PATH {
-            return Err(TryFromUrlError);
+        let mut update_builder = self.update(topic).privacy(privacy);
+        if let Some(data) = data {
+            update_builder = update_builder.data(data);
         }
-
-        Ok(Self(url))
+        update_builder.send().await
     }
-}
 
-impl fmt::Display for HubUrl {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{url}", url = self.0)
+    /// Publishes an update with the given revision ID to the Mercure hub.
+    ///
+    /// This is a shorthand for [`Client::update`] with
+    /// [`UpdateBuilder::id`]. See [`Client::publish_update`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::{PublishUpdatePrivacy, RevisionId};
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+    /// let data = r#"{"isbn":"9780735218789"}"#;
+    /// let privacy = PublishUpdatePrivacy::Public;
+    /// let id = RevisionId::try_from("urn:uuid:0b4b9a4c-0ea6-4b7f-9d4f-f1f1b4cf7d1e".to_owned())?;
+    ///
+    /// client.publish_update_with_id(topic, Some(&data), privacy, id).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_update_with_id(
+        &self,
+        topic: Topic,
+        data: Option<&str>,
+        privacy: PublishUpdatePrivacy,
+        id: RevisionId,
+    ) -> Result<RevisionId, PublishUpdateError> {
+        let mut update_builder = self.update(topic).privacy(privacy).id(id);
+        if let Some(data) = data {
+            update_builder = update_builder.data(data);
+        }
+        update_builder.send().await
     }
-}
 
-impl fmt::Display for TryFromUrlError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "the path of the URL must be {HUB_PATH}")
+    /// Publishes an update with the given type to the Mercure hub.
+    ///
+    /// The type is sent to subscribers in the SSE `event` field, and can be
+    /// used to dispatch updates by type.
+    ///
+    /// This is a shorthand for [`Client::update`] with
+    /// `UpdateBuilder::r#type`. See [`Client::publish_update`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+    /// let data = r#"{"isbn":"9780735218789"}"#;
+    /// let privacy = PublishUpdatePrivacy::Public;
+    ///
+    /// client
+    ///     .publish_update_with_type(topic, Some(&data), privacy, "BookUpdated")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_update_with_type(
+        &self,
+        topic: Topic,
+        data: Option<&str>,
+        privacy: PublishUpdatePrivacy,
+        r#type: &str,
+    ) -> Result<RevisionId, PublishUpdateError> {
+        let mut update_builder = self.update(topic).privacy(privacy).r#type(r#type);
+        if let Some(data) = data {
+            update_builder = update_builder.data(data);
+        }
+        update_builder.send().await
+    }
+
+    /// Publishes an update with the given reconnection time to the Mercure
+    /// hub.
+    ///
+    /// The reconnection time is sent to subscribers in the SSE `retry` field,
+    /// and sets how long they wait before reconnecting.
+    ///
+    /// This is a shorthand for [`Client::update`] with
+    /// [`UpdateBuilder::retry`]. See [`Client::publish_update`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// use std::time::Duration;
+    ///
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+    /// let data = r#"{"isbn":"9780735218789"}"#;
+    /// let privacy = PublishUpdatePrivacy::Public;
+    ///
+    /// client
+    ///     .publish_update_with_retry(topic, Some(&data), privacy, Duration::from_secs(10))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_update_with_retry(
+        &self,
+        topic: Topic,
+        data: Option<&str>,
+        privacy: PublishUpdatePrivacy,
+        retry: Duration,
+    ) -> Result<RevisionId, PublishUpdateError> {
+        let mut update_builder = self.update(topic).privacy(privacy).retry(retry);
+        if let Some(data) = data {
+            update_builder = update_builder.data(data);
+        }
+        update_builder.send().await
+    }
+
+    /// Publishes an update to the Mercure hub, taking ownership of the data.
+    ///
+    /// Unlike [`publish_update`], the returned future does not borrow the
+    /// client or the data, so it can be spawned, e.g. with `tokio::spawn`.
+    ///
+    /// [`publish_update`]: Self::publish_update
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let handles: Vec<_> = (1..=3)
+    ///     .map(|book_id| {
+    ///         let topic = Topic::new(format!("https://example.com/books/{book_id}").parse()?, vec![]);
+    ///         let data = format!(r#"{{"id":{book_id}}}"#);
+    ///         Ok(tokio::spawn(client.publish_update_owned(
+    ///             topic,
+    ///             Some(data),
+    ///             PublishUpdatePrivacy::Public,
+    ///         )))
+    ///     })
+    ///     .collect::<Result<_, url::ParseError>>()?;
+    /// for handle in handles {
+    ///     handle.await??;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn publish_update_owned(
+        &self,
+        topic: Topic,
+        data: Option<String>,
+        privacy: PublishUpdatePrivacy,
+    ) -> impl Future<Output = Result<RevisionId, PublishUpdateError>> + Send + 'static {
+        let client = self.clone();
+        async move { client.publish_update(topic, data.as_deref(), privacy).await }
+    }
+
+    /// Returns a builder for publishing an update to the Mercure hub.
+    ///
+    /// This allows setting the optional fields of the update, such as its
+    /// revision ID, type, and reconnection time.
+    /// As with [`Client::publish_update`], the topic can be passed by value or
+    /// by reference.
+    ///
+    /// [The Mercure Protocol, Section 5](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-5)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// use std::time::Duration;
+    ///
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+    /// let data = r#"{"isbn":"9780735218789"}"#;
+    ///
+    /// client
+    ///     .update(topic)
+    ///     .data(data)
+    ///     .private()
+    ///     .id("urn:uuid:0b4b9a4c-0ea6-4b7f-9d4f-f1f1b4cf7d1e".to_owned().try_into()?)
+    ///     .r#type("BookUpdated")
+    ///     .retry(Duration::from_secs(10))
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update<'a>(&'a self, topic: impl Into<Cow<'a, Topic>>) -> UpdateBuilder<'a, T> {
+        UpdateBuilder {
+            client: self,
+            params: PublishUpdateParams {
+                topic: topic.into(),
+                data: None,
+                privacy: PublishUpdatePrivacy::Public,
+                id: None,
+                r#type: None,
+                retry: None,
+            },
+            cancel_signal: None,
+        }
+    }
+
+    async fn publish(
+        &self,
+        params: PublishUpdateParams<'_>,
+    ) -> Result<RevisionId, PublishUpdateError> {
+        if self.check_topic_authorization {
+            self.authorize_topic(&params.topic)?;
+        }
+
+        #[cfg(feature = "tracing")]
+        let span = publish_span(params.topic.canonical_url(), params.privacy);
+        let publish =
+            self.send_publish_request(serde_html_form::to_string(params).map_err(|err| {
+                PublishUpdateError {
+                    kind: PublishUpdateErrorKind::SerializeParameters,
+                    inner: err.into(),
+                }
+            })?);
+        #[cfg(feature = "tracing")]
+        let publish = publish.instrument(span);
+        publish.await
+    }
+
+    /// Prepares publishing updates to the same topic, serializing the topic
+    /// and the privacy setting once.
+    ///
+    /// This avoids re-serializing the whole request body when only the data
+    /// changes between updates.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/stocks/ACME".parse()?, vec![]);
+    /// let privacy = PublishUpdatePrivacy::Public;
+    ///
+    /// let prepared_publish = client.prepare_publish(topic, privacy)?;
+    /// for price in ["42.00", "42.10", "41.95"] {
+    ///     prepared_publish.send(price).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prepare_publish(
+        &self,
+        topic: Topic,
+        privacy: PublishUpdatePrivacy,
+    ) -> Result<PreparedPublish<'_, T>, PublishUpdateError> {
+        if self.check_topic_authorization {
+            self.authorize_topic(&topic)?;
+        }
+
+        let params = PublishUpdateParams {
+            topic: Cow::Borrowed(&topic),
+            data: None,
+            privacy,
+            id: None,
+            r#type: None,
+            retry: None,
+        };
+
+        Ok(PreparedPublish {
+            client: self,
+            params: serde_html_form::to_string(params).map_err(|err| PublishUpdateError {
+                kind: PublishUpdateErrorKind::SerializeParameters,
+                inner: err.into(),
+            })?,
+            #[cfg(feature = "tracing")]
+            canonical_url: topic.canonical_url().clone(),
+            #[cfg(feature = "tracing")]
+            privacy,
+        })
+    }
+
+    /// Returns a `curl` command equivalent to publishing an update with
+    /// [`Client::publish_update`], e.g. for reproducing issues with the
+    /// operator of the Mercure hub.
+    ///
+    /// The publisher JWT in the `Authorization` header is redacted. See
+    /// [`Client::publish_update_as_curl_with_publisher_jwt`] to include it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+    /// let privacy = PublishUpdatePrivacy::Public;
+    ///
+    /// let curl_command = client.publish_update_as_curl(topic, Some("Hello!"), privacy)?;
+    /// assert!(curl_command.contains("Authorization: Bearer [REDACTED]"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn publish_update_as_curl(
+        &self,
+        topic: Topic,
+        data: Option<&str>,
+        privacy: PublishUpdatePrivacy,
+    ) -> Result<String, PublishUpdateError> {
+        self.curl_command(topic, data, privacy, "[REDACTED]")
+    }
+
+    /// Returns a `curl` command equivalent to publishing an update with
+    /// [`Client::publish_update`], including the publisher JWT.
+    ///
+    /// # Warning
+    ///
+    /// The returned command contains the publisher JWT, which can be used by
+    /// anyone to publish updates. It should only be used for local debugging.
+    pub fn publish_update_as_curl_with_publisher_jwt(
+        &self,
+        topic: Topic,
+        data: Option<&str>,
+        privacy: PublishUpdatePrivacy,
+    ) -> Result<String, PublishUpdateError> {
+        self.curl_command(topic, data, privacy, self.publisher_jwt.as_str())
+    }
+
+    fn curl_command(
+        &self,
+        topic: Topic,
+        data: Option<&str>,
+        privacy: PublishUpdatePrivacy,
+        publisher_jwt: &str,
+    ) -> Result<String, PublishUpdateError> {
+        fn quote(arg: &str) -> String {
+            format!("'{arg}'", arg = arg.replace('\'', r"'\''"))
+        }
+
+        let params = PublishUpdateParams {
+            topic: Cow::Owned(topic),
+            data: data.map(Cow::Borrowed),
+            privacy,
+            id: None,
+            r#type: None,
+            retry: None,
+        };
+        let body = serde_html_form::to_string(params).map_err(|err| PublishUpdateError {
+            kind: PublishUpdateErrorKind::SerializeParameters,
+            inner: err.into(),
+        })?;
+
+        Ok(format!(
+            "curl {url} \\\n  -H {authorization} \\\n  -H {content_type} \\\n  --data-raw {body}",
+            url = quote(self.hub_url.0.as_str()),
+            authorization = quote(&match self.authorization_mode {
+                AuthorizationMode::Header => format!("Authorization: Bearer {publisher_jwt}"),
+                AuthorizationMode::Cookie => {
+                    format!("Cookie: {MERCURE_AUTHORIZATION_COOKIE_NAME}={publisher_jwt}")
+                },
+            }),
+            content_type = quote("Content-Type: application/x-www-form-urlencoded"),
+            body = quote(&body),
+        ))
+    }
+
+    /// Checks that the publisher JWT authorizes publishing to all URLs of the
+    /// topic.
+    fn authorize_topic(&self, topic: &Topic) -> Result<(), PublishUpdateError> {
+        match topic
+            .iter()
+            .find(|url| !self.publisher_jwt.covers_topic(url))
+        {
+            Some(url) => Err(PublishUpdateError {
+                kind: PublishUpdateErrorKind::Unauthorized,
+                inner: url.to_string().into(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    async fn send_publish_request(&self, body: String) -> Result<RevisionId, PublishUpdateError> {
+        if !self.hub_url.is_secure() && !self.accept_insecure_hub_url {
+            return Err(PublishUpdateError {
+                kind: PublishUpdateErrorKind::InsecureHubUrl,
+                inner: "hub URL does not use HTTPS".into(),
+            });
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        match self.authorization_mode {
+            AuthorizationMode::Header => {
+                headers.insert(header::AUTHORIZATION, self.authorization_header());
+            },
+            AuthorizationMode::Cookie => {
+                headers.insert(header::COOKIE, self.authorization_cookie_header());
+            },
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(traceparent) = self
+            .traceparent
+            .as_ref()
+            .and_then(|traceparent| traceparent())
+        {
+            match HeaderValue::try_from(traceparent) {
+                Ok(traceparent) if is_valid_traceparent(traceparent.as_bytes()) => {
+                    headers.insert(HeaderName::from_static("traceparent"), traceparent);
+                },
+                _ => tracing::warn!("not sending invalid traceparent"),
+            }
+        }
+        let body = Bytes::from(body);
+
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        let mut attempt: u32 = 1;
+        let res = loop {
+            let req = TransportRequest::new(
+                self.hub_url.0.clone(),
+                headers.clone(),
+                body.clone(),
+                self.timeout,
+            );
+            let retry_delay = self
+                .retry_policy
+                .and_then(|retry_policy| retry_policy.delay(attempt));
+
+            match self.transport.post(req).await {
+                Ok(res) if res.status().is_server_error() && retry_delay.is_some() => {},
+                Ok(res) => break res,
+                Err(err) if T::is_connect_error(&err) && retry_delay.is_some() => {},
+                Err(err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(attempt, error = %err, "failed to send publish request");
+                    return Err(PublishUpdateError {
+                        kind: PublishUpdateErrorKind::SendRequest,
+                        inner: err.into(),
+                    });
+                },
+            }
+
+            if let Some(retry_delay) = retry_delay {
+                #[cfg(feature = "tracing")]
+                tracing::info!(attempt, ?retry_delay, "retrying publish request");
+                time::sleep(retry_delay).await;
+            }
+            attempt = attempt.saturating_add(1);
+        };
+
+        let status = res.status();
+        let location = res
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .filter(|location| !location.is_empty())
+            .map(ToOwned::to_owned);
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            attempt,
+            status = status.as_u16(),
+            latency = ?start.elapsed(),
+            "received publish response"
+        );
+        let body: Vec<u8> = Box::pin(res.into_body())
+            .try_fold(Vec::new(), |mut body, chunk| async move {
+                body.extend_from_slice(&chunk);
+                Ok(body)
+            })
+            .await
+            .map_err(|err| PublishUpdateError {
+                kind: PublishUpdateErrorKind::ReadResponse,
+                inner: err.into(),
+            })?;
+        let body = String::from_utf8_lossy(&body).into_owned();
+        if !status.is_success() {
+            return Err(PublishUpdateError {
+                kind: PublishUpdateErrorKind::UnexpectedStatus { status, body },
+                inner: "Mercure hub responded with an unexpected status".into(),
+            });
+        }
+
+        Ok(RevisionId(location.unwrap_or(body)))
+    }
+
+    /// Publishes multiple updates to the Mercure hub.
+    ///
+    /// The Mercure hub does not support publishing multiple updates in a
+    /// single request, so the publish requests are sent concurrently instead,
+    /// up to the limit set by [`ClientBuilder::max_concurrent_publishes`].
+    ///
+    /// Each update is given as its topic, data and privacy setting, as for
+    /// [`Client::publish_update`], and is published through this client.
+    ///
+    /// Returns the result of publishing each update, in the same order as the
+    /// updates.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let books = [("1", r#"{"isbn":"9780735218789"}"#), ("2", r#"{"isbn":"9780593230251"}"#)];
+    /// let topics = books
+    ///     .iter()
+    ///     .map(|(id, _)| Ok(Topic::new(format!("https://example.com/books/{id}").parse()?, vec![])))
+    ///     .collect::<Result<Vec<_>, url::ParseError>>()?;
+    ///
+    /// let results = client
+    ///     .publish_updates(
+    ///         topics
+    ///             .into_iter()
+    ///             .zip(books)
+    ///             .map(|(topic, (_, data))| (topic, Some(data), PublishUpdatePrivacy::Public)),
+    ///     )
+    ///     .await;
+    /// for result in results {
+    ///     result?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_updates<I, D>(
+        &self,
+        updates: I,
+    ) -> Vec<Result<RevisionId, PublishUpdateError>>
+    where
+        I: IntoIterator<Item = (Topic, Option<D>, PublishUpdatePrivacy)>,
+        D: AsRef<str>,
+    {
+        stream::iter(updates)
+            .map(|(topic, data, privacy)| async move {
+                self.publish_update(topic, data.as_ref().map(AsRef::as_ref), privacy)
+                    .await
+            })
+            .buffered(
+                self.max_concurrent_publishes
+                    .unwrap_or(DEFAULT_MAX_CONCURRENT_PUBLISHES)
+                    .get(),
+            )
+            .collect()
+            .await
+    }
+
+    /// Spawns a background task which owns the client and publishes updates
+    /// sent through the returned [`Publisher`] handle.
+    ///
+    /// Up to `capacity` updates can be queued before [`Publisher::send`] waits
+    /// for the background task to catch up. The queued updates are published
+    /// concurrently, up to the limit set by
+    /// [`ClientBuilder::max_concurrent_publishes`].
+    ///
+    /// Call [`PublisherTask::shutdown`] to stop accepting updates and wait for
+    /// the pending updates to be published.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// use std::num::NonZeroUsize;
+    ///
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let (publisher, task) = client.spawn_publisher(NonZeroUsize::new(64).unwrap());
+    ///
+    /// let producers = (1..=3)
+    ///     .map(|book_id| {
+    ///         let publisher = publisher.clone();
+    ///         tokio::spawn(async move {
+    ///             let topic =
+    ///                 Topic::new(format!("https://example.com/books/{book_id}").parse()?, vec![]);
+    ///             let data = format!(r#"{{"id":{book_id}}}"#);
+    ///             publisher
+    ///                 .send(topic, Some(data), PublishUpdatePrivacy::Public)
+    ///                 .await?;
+    ///             Ok::<_, Box<dyn Error + Send + Sync>>(())
+    ///         })
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// for producer in producers {
+    ///     producer.await?.map_err(|err| err as Box<dyn Error>)?;
+    /// }
+    ///
+    /// task.shutdown().await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn_publisher(self, capacity: NonZeroUsize) -> (Publisher, PublisherTask) {
+        let (sender, mut receiver) = mpsc::channel::<QueuedUpdate>(capacity.get());
+        let (shutdown, mut shutdown_receiver) = oneshot::channel();
+        let limit = self
+            .max_concurrent_publishes
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_PUBLISHES)
+            .get();
+        let mut shutting_down = false;
+        let updates = stream::poll_fn(move |cx| {
+            // Once shutdown is requested, stop accepting updates, but keep
+            // draining the ones already queued.
+            if !shutting_down {
+                if let Poll::Ready(res) = Pin::new(&mut shutdown_receiver).poll(cx) {
+                    shutting_down = true;
+                    if res.is_ok() {
+                        receiver.close();
+                    }
+                }
+            }
+            receiver.poll_recv(cx)
+        });
+        let handle = tokio::spawn(async move {
+            updates
+                .for_each_concurrent(limit, |update| {
+                    let client = &self;
+                    async move {
+                        let res = client
+                            .publish_update(update.topic, update.data.as_deref(), update.privacy)
+                            .await;
+                        // The sender may have stopped waiting for the result.
+                        let _ = update.result.send(res);
+                    }
+                })
+                .await;
+        });
+
+        (Publisher { sender }, PublisherTask { shutdown, handle })
+    }
+
+    /// Publishes an update without data to the Mercure hub, signalling that
+    /// the resource identified by the topic has been deleted.
+    ///
+    /// Before sending the update, checks that the publisher JWT authorizes
+    /// publishing to all URLs of the topic, failing with
+    /// [`PublishUpdateErrorKind::Unauthorized`] otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/users/1/books/1".parse()?, vec![]);
+    /// let privacy = PublishUpdatePrivacy::Private;
+    ///
+    /// client.publish_delete(topic, privacy).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_delete(
+        &self,
+        topic: Topic,
+        privacy: PublishUpdatePrivacy,
+    ) -> Result<RevisionId, PublishUpdateError> {
+        self.authorize_topic(&topic)?;
+
+        self.publish_ping(topic, privacy).await
+    }
+
+    /// Publishes an update without data to the Mercure hub, signalling that
+    /// the resource identified by the topic has changed, e.g. so that
+    /// subscribers refetch it.
+    ///
+    /// The "data" field is omitted rather than sent empty. Subscribers receive
+    /// an event with empty data either way, so the topic (or the type of the
+    /// update) must tell them what to do.
+    ///
+    /// This is the same as calling [`Client::publish_update`] with `None` as
+    /// the data. See also [`Client::publish_delete`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::PublishUpdatePrivacy;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+    /// let privacy = PublishUpdatePrivacy::Public;
+    ///
+    /// client.publish_ping(topic, privacy).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_ping(
+        &self,
+        topic: Topic,
+        privacy: PublishUpdatePrivacy,
+    ) -> Result<RevisionId, PublishUpdateError> {
+        self.publish_update(topic, None, privacy).await
+    }
+
+    /// Subscribes to updates from the Mercure hub.
+    ///
+    /// [The Mercure Protocol, Section 4](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-4)
+    ///
+    /// If `last_event_id` is provided, it is sent in the `Last-Event-ID`
+    /// header, so that the Mercure hub sends the updates published since then.
+    /// The returned [`UpdateStream`] reconnects automatically in the same way.
+    ///
+    /// # Note
+    ///
+    /// The publisher JWT is not sent, so only public updates are received.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::StreamExt as _;
+    /// use mercure::TopicSelector;
+    /// # use mercure::{HubUrl, PublisherJwt};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic_selectors = vec![TopicSelector::UriTemplate(
+    ///     "https://example.com/books/{book_id}".try_into()?,
+    /// )];
+    ///
+    /// let mut update_stream = client.subscribe(topic_selectors, None).await?;
+    /// while let Some(update) = update_stream.next().await {
+    ///     let update = update?;
+    ///     println!("{data}", data = update.data());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe(
+        &self,
+        topic_selectors: Vec<TopicSelector>,
+        last_event_id: Option<&str>,
+    ) -> Result<UpdateStream<T>, SubscribeError> {
+        let mut subscription_builder = self.subscription(topic_selectors);
+        if let Some(last_event_id) = last_event_id {
+            subscription_builder = subscription_builder.last_event_id(last_event_id);
+        }
+        subscription_builder.subscribe().await
+    }
+
+    /// Returns a builder for subscribing to updates from the Mercure hub.
+    ///
+    /// This allows setting the last event ID, and adding extra query
+    /// parameters and headers, e.g. for extensions supported by the Mercure
+    /// hub. See [`SubscriptionBuilder`] for how they interact with the query
+    /// parameters and headers set by this crate.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::StreamExt as _;
+    /// use mercure::TopicSelector;
+    /// # use mercure::{HubUrl, PublisherJwt};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic_selectors = vec![TopicSelector::UriTemplate(
+    ///     "https://example.com/books/{book_id}".try_into()?,
+    /// )];
+    ///
+    /// let mut update_stream = client
+    ///     .subscription(topic_selectors)
+    ///     .last_event_id("urn:uuid:0b4b9a4c-0ea6-4b7f-9d4f-f1f1b4cf7d1e")
+    ///     .query_param("lang", "en")
+    ///     .header("x-request-id".parse()?, "42".parse()?)
+    ///     .subscribe()
+    ///     .await?;
+    /// while let Some(update) = update_stream.next().await {
+    ///     let update = update?;
+    ///     println!("{data}", data = update.data());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subscription(&self, topic_selectors: Vec<TopicSelector>) -> SubscriptionBuilder<'_, T> {
+        SubscriptionBuilder {
+            client: self,
+            topic_selectors,
+            last_event_id: None,
+            query_params: Vec::new(),
+            headers: HeaderMap::new(),
+            #[cfg(feature = "compression")]
+            compression: true,
+            reconnect_policy: ReconnectPolicy::default(),
+            connect_timeout: None,
+            cancel_signal: None,
+        }
+    }
+
+    /// Returns the value of the `Authorization` header sent to the Mercure
+    /// hub, which is built from the publisher JWT.
+    ///
+    /// It is not sent in [`AuthorizationMode::Cookie`].
+    ///
+    /// The returned [`HeaderValue`] is marked as sensitive.
+    pub fn authorization_header(&self) -> HeaderValue {
+        let mut header_value: HeaderValue =
+            format!("Bearer {jwt}", jwt = self.publisher_jwt.as_str())
+                .parse()
+                .expect("`publisher_jwt` should not contain invalid ASCII");
+        header_value.set_sensitive(true);
+        header_value
+    }
+
+    /// Returns the value of the `Cookie` header sent to the Mercure hub in
+    /// [`AuthorizationMode::Cookie`].
+    fn authorization_cookie_header(&self) -> HeaderValue {
+        let mut header_value: HeaderValue = format!(
+            "{MERCURE_AUTHORIZATION_COOKIE_NAME}={jwt}",
+            jwt = self.publisher_jwt.as_str()
+        )
+        .parse()
+        .expect("`publisher_jwt` should not contain invalid ASCII");
+        header_value.set_sensitive(true);
+        header_value
+    }
+}
+
+impl UpdateData {
+    /// Returns the data as sent in the "data" field.
+    pub fn into_string(self) -> String {
+        match self {
+            Self::Json(value) => value.to_string(),
+            Self::Text(data) | Self::Raw(data) => data,
+        }
+    }
+}
+
+impl From<serde_json::Value> for UpdateData {
+    fn from(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl<'a, T> UpdateBuilder<'a, T>
+where
+    T: Transport,
+{
+    /// Sets the data of the update.
+    pub fn data(mut self, data: &'a str) -> Self {
+        self.params.data = Some(Cow::Borrowed(data));
+        self
+    }
+
+    /// Sets the data of the update from [`UpdateData`], e.g. to serialize a
+    /// JSON value.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::UpdateData;
+    /// use mercure::Topic;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+    /// let data = UpdateData::Json(serde_json::json!({
+    ///     "@id": "https://example.com/books/1",
+    ///     "isbn": "9780735218789",
+    /// }));
+    ///
+    /// client.update(topic).typed_data(data).send().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn typed_data(mut self, data: UpdateData) -> Self {
+        self.params.data = Some(Cow::Owned(data.into_string()));
+        self
+    }
+
+    /// Sets the privacy setting of the update.
+    pub fn privacy(mut self, privacy: PublishUpdatePrivacy) -> Self {
+        self.params.privacy = privacy;
+        self
+    }
+
+    /// Marks the update as private.
+    ///
+    /// See [`PublishUpdatePrivacy::Private`].
+    pub fn private(self) -> Self {
+        self.privacy(PublishUpdatePrivacy::Private)
+    }
+
+    /// Sets the revision ID of the update.
+    pub fn id(mut self, id: RevisionId) -> Self {
+        self.params.id = Some(id);
+        self
+    }
+
+    /// Sets the type of the update.
+    ///
+    /// The type is sent to subscribers in the SSE `event` field, and can be
+    /// used to dispatch updates by type.
+    pub fn r#type(mut self, r#type: &'a str) -> Self {
+        self.params.r#type = Some(r#type);
+        self
+    }
+
+    /// Sets the reconnection time of the update.
+    ///
+    /// The reconnection time is sent to subscribers in the SSE `retry` field,
+    /// and sets how long they wait before reconnecting.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.params.retry = Some(retry);
+        self
+    }
+
+    /// Cancels publishing when the given future resolves, e.g.
+    /// `CancellationToken::cancelled` from `tokio-util`, aborting the
+    /// in-flight request.
+    ///
+    /// [`UpdateBuilder::send`] then fails with
+    /// [`PublishUpdateErrorKind::Cancelled`].
+    pub fn cancel_on<F>(mut self, cancelled: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'a,
+    {
+        self.cancel_signal = Some(CancelSignal(Box::pin(cancelled)));
+        self
+    }
+
+    /// Publishes the update to the Mercure hub.
+    ///
+    /// See [`Client::publish_update`].
+    pub async fn send(self) -> Result<RevisionId, PublishUpdateError> {
+        let publish = self.client.publish(self.params);
+        let Some(cancel_signal) = self.cancel_signal else {
+            return publish.await;
+        };
+
+        match future::select(cancel_signal, pin!(publish)).await {
+            Either::Left(((), _)) => Err(PublishUpdateError {
+                kind: PublishUpdateErrorKind::Cancelled,
+                inner: "publishing was cancelled".into(),
+            }),
+            Either::Right((res, _)) => res,
+        }
+    }
+}
+
+impl<'a, T> SubscriptionBuilder<'a, T>
+where
+    T: Transport,
+{
+    /// Sets the last event ID, sent in the `Last-Event-ID` header, so that
+    /// the Mercure hub sends the updates published since then.
+    pub fn last_event_id(mut self, last_event_id: &'a str) -> Self {
+        self.last_event_id = Some(last_event_id);
+        self
+    }
+
+    /// Adds an extra query parameter to the subscription URL, also sent when
+    /// reconnecting.
+    ///
+    /// The last event ID cannot be given as a `lastEventID` query parameter,
+    /// use [`SubscriptionBuilder::last_event_id`] instead. See
+    /// [`SubscriptionBuilder`] for precedence.
+    pub fn query_param(mut self, name: &'a str, value: &'a str) -> Self {
+        self.query_params.push((name, value));
+        self
+    }
+
+    /// Adds an extra header to the subscribe request, also sent when
+    /// reconnecting.
+    ///
+    /// The last event ID cannot be given as a `Last-Event-ID` header, use
+    /// [`SubscriptionBuilder::last_event_id`] instead. See
+    /// [`SubscriptionBuilder`] for precedence.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Sets whether the transport may ask the Mercure hub to compress the
+    /// event stream. Enabled by default.
+    ///
+    /// Compression is negotiated by the transport, which decompresses the
+    /// event stream before it is parsed, see
+    /// [`TransportRequest::accept_compression`]. [`ReqwestTransport`] asks for
+    /// gzip or deflate, unless they were disabled when building the
+    /// [`reqwest::Client`].
+    ///
+    /// Disable it for Mercure hubs which mishandle compressed event streams.
+    /// The transport then asks for an uncompressed event stream with
+    /// `Accept-Encoding: identity`, unless an extra `Accept-Encoding` header
+    /// is given.
+    #[cfg(feature = "compression")]
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the [`ReconnectPolicy`] of the returned [`UpdateStream`].
+    ///
+    /// Defaults to [`ReconnectPolicy::default`].
+    pub fn reconnect_policy(mut self, reconnect_policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = reconnect_policy;
+        self
+    }
+
+    /// Sets the timeout for connecting to the Mercure hub, from when the
+    /// subscribe request starts until the response headers are received.
+    ///
+    /// If it elapses, [`SubscriptionBuilder::subscribe`] fails with
+    /// [`SubscribeErrorKind::ConnectTimeout`], so that an unreachable Mercure
+    /// hub is detected quickly. It does not apply once connected, as there
+    /// may be long gaps between updates, nor when reconnecting. No timeout is
+    /// set by default.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Cancels the subscription when the given future resolves, e.g.
+    /// `CancellationToken::cancelled` from `tokio-util`.
+    ///
+    /// If it resolves while subscribing, [`SubscriptionBuilder::subscribe`]
+    /// fails with [`SubscribeErrorKind::Cancelled`]. Afterwards, the returned
+    /// [`UpdateStream`] closes the connection and ends.
+    pub fn cancel_on<F>(mut self, cancelled: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.cancel_signal = Some(CancelSignal(Box::pin(cancelled)));
+        self
+    }
+
+    /// Subscribes to updates from the Mercure hub.
+    ///
+    /// See [`Client::subscribe`].
+    pub async fn subscribe(mut self) -> Result<UpdateStream<T>, SubscribeError> {
+        let extra = if self
+            .query_params
+            .iter()
+            .any(|&(name, _)| name == "lastEventID")
+        {
+            Some("`lastEventID` query parameter")
+        } else if self.headers.contains_key("last-event-id") {
+            Some("`Last-Event-ID` header")
+        } else {
+            None
+        };
+        if let Some(extra) = extra {
+            return Err(SubscribeError {
+                kind: SubscribeErrorKind::ExtraLastEventId,
+                inner: extra.into(),
+            });
+        }
+
+        let mut url = self.client.hub_url.0.clone();
+        url.query_pairs_mut()
+            .extend_pairs(
+                self.topic_selectors
+                    .iter()
+                    .map(|topic_selector| ("topic", topic_selector.to_string())),
+            )
+            .extend_pairs(self.query_params);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        headers.extend(self.headers);
+        let request = TransportRequest::new(url, headers, Bytes::new(), None);
+        #[cfg(feature = "compression")]
+        let request = request.with_accept_compression(self.compression);
+        let mut first_request = request.clone();
+        if let Some(last_event_id) = self.last_event_id {
+            first_request.headers_mut().insert(
+                "last-event-id",
+                last_event_id.parse().map_err(|err| SubscribeError {
+                    kind: SubscribeErrorKind::InvalidLastEventId,
+                    inner: Box::new(err),
+                })?,
+            );
+        }
+
+        let transport = &self.client.transport;
+        let connect_timeout = self.connect_timeout;
+        let get = async move {
+            let get = transport.get(first_request);
+            let res = match connect_timeout {
+                Some(connect_timeout) => {
+                    time::timeout(connect_timeout, get)
+                        .await
+                        .ok_or_else(|| SubscribeError {
+                            kind: SubscribeErrorKind::ConnectTimeout,
+                            inner: format!("{connect_timeout:?}").into(),
+                        })?
+                },
+                None => get.await,
+            };
+            res.map_err(|err| SubscribeError {
+                kind: SubscribeErrorKind::SendRequest,
+                inner: err.into(),
+            })
+        };
+        let res = match self.cancel_signal.as_mut() {
+            Some(cancel_signal) => match future::select(cancel_signal, pin!(get)).await {
+                Either::Left(((), _)) => {
+                    return Err(SubscribeError {
+                        kind: SubscribeErrorKind::Cancelled,
+                        inner: "subscribing was cancelled".into(),
+                    });
+                },
+                Either::Right((res, _)) => res,
+            },
+            None => get.await,
+        }?;
+        if !res.status().is_success() {
+            return Err(SubscribeError {
+                kind: SubscribeErrorKind::ErrorStatus,
+                inner: res.status().to_string().into(),
+            });
+        }
+
+        Ok(UpdateStream::new(
+            self.client.transport.clone(),
+            request,
+            self.last_event_id.map(ToOwned::to_owned),
+            res,
+        )
+        .with_reconnect_policy(self.reconnect_policy)
+        .with_cancel_signal(self.cancel_signal))
+    }
+}
+
+impl<T> PreparedPublish<'_, T>
+where
+    T: Transport,
+{
+    /// Publishes an update with the given data to the Mercure hub.
+    ///
+    /// See [`Client::publish_update`].
+    pub async fn send(&self, data: &str) -> Result<RevisionId, PublishUpdateError> {
+        let publish = self.client.send_publish_request(self.body(data));
+        #[cfg(feature = "tracing")]
+        let publish = publish.instrument(publish_span(&self.canonical_url, self.privacy));
+        publish.await
+    }
+
+    fn body(&self, data: &str) -> String {
+        form_urlencoded::Serializer::for_suffix(self.params.clone(), 0)
+            .append_pair("data", data)
+            .finish()
+    }
+}
+
+impl Publisher {
+    /// Queues an update to be published to the Mercure hub by the background
+    /// task, and waits for the result.
+    ///
+    /// Waits for space in the queue if it is full.
+    ///
+    /// See [`Client::publish_update`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PublishUpdateErrorKind::PublisherShutDown`] if the
+    /// background task has been shut down.
+    pub async fn send(
+        &self,
+        topic: Topic,
+        data: Option<String>,
+        privacy: PublishUpdatePrivacy,
+    ) -> Result<RevisionId, PublishUpdateError> {
+        let shut_down = || PublishUpdateError {
+            kind: PublishUpdateErrorKind::PublisherShutDown,
+            inner: "publisher has been shut down".into(),
+        };
+        let (result, result_receiver) = oneshot::channel();
+        self.sender
+            .send(QueuedUpdate {
+                topic,
+                data,
+                privacy,
+                result,
+            })
+            .await
+            .map_err(|_| shut_down())?;
+        result_receiver.await.map_err(|_| shut_down())?
+    }
+}
+
+impl PublisherTask {
+    /// Stops accepting new updates, and waits for the updates already queued
+    /// to be published.
+    ///
+    /// Sending more updates through any [`Publisher`] handle fails with
+    /// [`PublishUpdateErrorKind::PublisherShutDown`] afterwards.
+    pub async fn shutdown(self) {
+        // The background task may have stopped already, if all handles have
+        // been dropped.
+        let _ = self.shutdown.send(());
+        if let Err(err) = self.handle.await {
+            if err.is_panic() {
+                std::panic::resume_unwind(err.into_panic());
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for GenericClient<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug_struct = f.debug_struct("GenericClient");
+        debug_struct
+            .field("transport", &self.transport)
+            .field("hub_url", &self.hub_url)
+            .field("publisher_jwt", &"[REDACTED]")
+            .field("accept_insecure_hub_url", &self.accept_insecure_hub_url)
+            .field("timeout", &self.timeout)
+            .field("retry_policy", &self.retry_policy)
+            .field("max_concurrent_publishes", &self.max_concurrent_publishes)
+            .field("authorization_mode", &self.authorization_mode)
+            .field("check_topic_authorization", &self.check_topic_authorization);
+        #[cfg(feature = "tracing")]
+        debug_struct.field("traceparent", &self.traceparent.as_ref().map(|_| ".."));
+        debug_struct.finish()
+    }
+}
+
+impl ClientBuilder {
+    /// Constructs a new `ClientBuilder`.
+    pub fn new(hub_url: HubUrl, publisher_jwt: PublisherJwt) -> Self {
+        Self {
+            transport: ReqwestTransport::default(),
+            hub_url,
+            publisher_jwt,
+            accept_insecure_hub_url: false,
+            timeout: None,
+            retry_policy: None,
+            max_concurrent_publishes: None,
+            authorization_mode: AuthorizationMode::Header,
+            check_topic_authorization: false,
+            #[cfg(feature = "tracing")]
+            traceparent: None,
+        }
+    }
+
+    /// Sets the HTTP client to use. Defaults to `reqwest::Client::new()`.
+    ///
+    /// # HTTP/2
+    ///
+    /// Each [`UpdateStream`] keeps its subscribe request open for as long as it
+    /// is alive. Over HTTP/1.1, that takes up a connection per subscription.
+    /// Over HTTP/2, all subscriptions and publish requests sent with the same
+    /// `reqwest::Client` are multiplexed as streams over a single connection to
+    /// the hub. `reqwest::Client` is cheap to clone, so build it once and pass a
+    /// clone of it to each `ClientBuilder`.
+    ///
+    /// This crate does not enable the `http2` feature of `reqwest`, so enable it
+    /// in your own `Cargo.toml` to use HTTP/2. Over TLS, HTTP/2 is negotiated
+    /// with ALPN. A hub serving HTTP/2 over cleartext (h2c) cannot be
+    /// negotiated with, so use `reqwest::ClientBuilder::http2_prior_knowledge`,
+    /// which makes every request fail against a hub that only speaks HTTP/1.1.
+    ///
+    /// HTTP/2 flow control limits how much data the hub may send on each
+    /// stream, and on the connection as a whole, before the client has read it.
+    /// A subscription whose `UpdateStream` is not polled promptly uses up its
+    /// stream window, and then the shared connection window, at which point
+    /// updates for every other subscription on that connection are held back
+    /// at the hub too. With small windows, a burst of large updates also takes
+    /// extra round trips to deliver. Keep every `UpdateStream` polled, and
+    /// raise the windows with `http2_initial_stream_window_size` and
+    /// `http2_initial_connection_window_size`, or let them grow with
+    /// `http2_adaptive_window`, if subscriptions receive large updates.
+    ///
+    /// ```ignore
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::{ClientBuilder, HubUrl, PublisherJwt, TopicSelector};
+    /// use url::Url;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// // Requires the `http2` feature of `reqwest`.
+    /// let http_client = reqwest::Client::builder()
+    ///     .http2_prior_knowledge()
+    ///     .http2_initial_stream_window_size(1024 * 1024)
+    ///     .http2_initial_connection_window_size(4 * 1024 * 1024)
+    ///     .build()?;
+    /// let hub_url = HubUrl::try_from("http://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// let publisher_jwt = PublisherJwt::new(
+    ///     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    ///     vec![TopicSelector::Wildcard],
+    /// )?;
+    ///
+    /// let client = ClientBuilder::new(hub_url, publisher_jwt)
+    ///     .danger_accept_insecure_hub_url(true)
+    ///     .http_client(http_client.clone())
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.transport = ReqwestTransport::new(http_client);
+        self
+    }
+}
+
+impl<T> ClientBuilder<T>
+where
+    T: Transport,
+{
+    /// Sets the [`Transport`] to use instead of [`ReqwestTransport`].
+    pub fn transport<U>(self, transport: U) -> ClientBuilder<U>
+    where
+        U: Transport,
+    {
+        ClientBuilder {
+            transport,
+            hub_url: self.hub_url,
+            publisher_jwt: self.publisher_jwt,
+            accept_insecure_hub_url: self.accept_insecure_hub_url,
+            timeout: self.timeout,
+            retry_policy: self.retry_policy,
+            max_concurrent_publishes: self.max_concurrent_publishes,
+            authorization_mode: self.authorization_mode,
+            check_topic_authorization: self.check_topic_authorization,
+            #[cfg(feature = "tracing")]
+            traceparent: self.traceparent,
+        }
+    }
+
+    /// Sets the timeout for each publish request, from when the request starts
+    /// connecting until the response body has finished.
+    ///
+    /// The timeout does not apply to subscriptions, which are long-lived.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the policy for retrying a publish request after a transient
+    /// failure. Publish requests are not retried by default.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sets the maximum number of publish requests sent concurrently by
+    /// [`Client::publish_updates`]. Defaults to 16.
+    pub fn max_concurrent_publishes(mut self, max_concurrent_publishes: NonZeroUsize) -> Self {
+        self.max_concurrent_publishes = Some(max_concurrent_publishes);
+        self
+    }
+
+    /// Sets how the publisher JWT is presented to the Mercure hub when
+    /// publishing. Defaults to [`AuthorizationMode::Header`].
+    pub fn authorization_mode(mut self, authorization_mode: AuthorizationMode) -> Self {
+        self.authorization_mode = authorization_mode;
+        self
+    }
+
+    /// Controls whether to check that the publisher JWT authorizes publishing
+    /// to all URLs of the topic before sending an update, failing with
+    /// [`PublishUpdateErrorKind::Unauthorized`] otherwise. Disabled by
+    /// default.
+    ///
+    /// This catches misconfiguration without a round trip to the Mercure hub,
+    /// which would respond with 403. The check uses the "mercure.publish"
+    /// claim of the publisher JWT held by the client, so the secret key is
+    /// not needed.
+    ///
+    /// [`Client::publish_delete`] always performs this check.
+    pub fn check_topic_authorization(mut self, check_topic_authorization: bool) -> Self {
+        self.check_topic_authorization = check_topic_authorization;
+        self
+    }
+
+    /// See [`Client::danger_accept_insecure_hub_url`].
+    pub fn danger_accept_insecure_hub_url(mut self, accept_insecure_hub_url: bool) -> Self {
+        self.accept_insecure_hub_url = accept_insecure_hub_url;
+        self
+    }
+
+    /// Sets the function returning the W3C `traceparent` header value to send
+    /// with each publish request, for propagating the trace context to the
+    /// Mercure hub.
+    ///
+    /// The function is called in the `mercure.publish` span, so it can build
+    /// the value from the current span, e.g. with the OpenTelemetry context of
+    /// [`tracing-opentelemetry`]. The header is not sent if the function
+    /// returns `None`, or a value which is not a valid `traceparent`.
+    ///
+    /// [`tracing-opentelemetry`]: https://docs.rs/tracing-opentelemetry
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    /// #
+    /// /// Returns the `traceparent` of the current span, e.g. with the
+    /// /// `TraceContextPropagator` of OpenTelemetry.
+    /// fn current_traceparent() -> Option<String> {
+    ///     // ...
+    /// #     None
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// let client = mercure::Client::builder(hub_url, publisher_jwt)
+    ///     .traceparent(current_traceparent)
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn traceparent<F>(mut self, traceparent: F) -> Self
+    where
+        F: Fn() -> Option<String> + Send + Sync + 'static,
+    {
+        self.traceparent = Some(std::sync::Arc::new(traceparent));
+        self
+    }
+
+    /// Returns a [`GenericClient`] with this configuration.
+    pub fn build(self) -> GenericClient<T> {
+        GenericClient {
+            transport: self.transport,
+            hub_url: self.hub_url,
+            publisher_jwt: self.publisher_jwt,
+            accept_insecure_hub_url: self.accept_insecure_hub_url,
+            timeout: self.timeout,
+            retry_policy: self.retry_policy,
+            max_concurrent_publishes: self.max_concurrent_publishes,
+            authorization_mode: self.authorization_mode,
+            check_topic_authorization: self.check_topic_authorization,
+            #[cfg(feature = "tracing")]
+            traceparent: self.traceparent,
+        }
+    }
+}
+
+impl<T> fmt::Debug for ClientBuilder<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug_struct = f.debug_struct("ClientBuilder");
+        debug_struct
+            .field("transport", &self.transport)
+            .field("hub_url", &self.hub_url)
+            .field("publisher_jwt", &"[REDACTED]")
+            .field("accept_insecure_hub_url", &self.accept_insecure_hub_url)
+            .field("timeout", &self.timeout)
+            .field("retry_policy", &self.retry_policy)
+            .field("max_concurrent_publishes", &self.max_concurrent_publishes)
+            .field("authorization_mode", &self.authorization_mode)
+            .field("check_topic_authorization", &self.check_topic_authorization);
+        #[cfg(feature = "tracing")]
+        debug_struct.field("traceparent", &self.traceparent.as_ref().map(|_| ".."));
+        debug_struct.finish()
+    }
+}
+
+impl RetryPolicy {
+    /// Constructs a new `RetryPolicy`.
+    ///
+    /// `max_attempts` includes the first attempt, so a value of `1` (or `0`)
+    /// disables retrying.
+    pub const fn new(max_attempts: u32, backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            backoff,
+        }
+    }
+
+    /// Returns the maximum number of attempts, including the first attempt.
+    pub const fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the delay before the first retry.
+    pub const fn backoff(&self) -> Duration {
+        self.backoff
+    }
+
+    /// Returns the delay before retrying after the given failed attempt
+    /// (starting from 1), or `None` if there are no attempts left.
+    fn delay(&self, attempt: u32) -> Option<Duration> {
+        (attempt < self.max_attempts).then(|| {
+            self.backoff
+                .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+        })
+    }
+}
+
+impl TryFrom<UncheckedClientConfig> for ClientConfig {
+    type Error = TryFromUrlError;
+
+    fn try_from(client_config: UncheckedClientConfig) -> Result<Self, Self::Error> {
+        let hub_url = if client_config.hub_url_unchecked {
+            HubUrl::new_unchecked(client_config.hub_url)
+        } else {
+            HubUrl::try_from(client_config.hub_url)?
+        };
+
+        Ok(Self {
+            hub_url,
+            hub_url_unchecked: client_config.hub_url_unchecked,
+            accept_insecure_hub_url: client_config.accept_insecure_hub_url,
+            timeout: client_config.timeout,
+            retry_policy: client_config.retry_policy,
+            max_concurrent_publishes: client_config.max_concurrent_publishes,
+            authorization_mode: client_config.authorization_mode,
+            check_topic_authorization: client_config.check_topic_authorization,
+        })
+    }
+}
+
+impl ClientConfig {
+    /// Returns the URL of the Mercure hub.
+    pub fn hub_url(&self) -> &HubUrl {
+        &self.hub_url
+    }
+
+    /// Returns whether the publisher JWT may be sent to a Mercure hub over
+    /// plaintext HTTP.
+    ///
+    /// See [`Client::danger_accept_insecure_hub_url`].
+    pub fn accept_insecure_hub_url(&self) -> bool {
+        self.accept_insecure_hub_url
+    }
+
+    /// Returns the timeout for each publish request, if any.
+    ///
+    /// See [`ClientBuilder::timeout`].
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the policy for retrying a publish request, if any.
+    ///
+    /// See [`ClientBuilder::retry_policy`].
+    pub fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.retry_policy
+    }
+
+    /// Returns the maximum number of publish requests sent concurrently, if
+    /// configured.
+    ///
+    /// See [`ClientBuilder::max_concurrent_publishes`].
+    pub fn max_concurrent_publishes(&self) -> Option<NonZeroUsize> {
+        self.max_concurrent_publishes
+    }
+
+    /// Returns how the publisher JWT is presented to the Mercure hub when
+    /// publishing.
+    ///
+    /// See [`ClientBuilder::authorization_mode`].
+    pub fn authorization_mode(&self) -> AuthorizationMode {
+        self.authorization_mode
+    }
+
+    /// Returns whether the publisher JWT is checked to authorize publishing to
+    /// the topic before sending an update.
+    ///
+    /// See [`ClientBuilder::check_topic_authorization`].
+    pub fn check_topic_authorization(&self) -> bool {
+        self.check_topic_authorization
+    }
+}
+
+impl AuthorizationMode {
+    const fn is_header(&self) -> bool {
+        matches!(self, Self::Header)
+    }
+}
+
+const fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Returns the span for publishing an update, carrying the canonical URL of
+/// the topic and the privacy setting, but never the publisher JWT or the data.
+#[cfg(feature = "tracing")]
+fn publish_span(canonical_url: &Url, privacy: PublishUpdatePrivacy) -> tracing::Span {
+    tracing::info_span!("mercure.publish", topic = %canonical_url, ?privacy)
+}
+
+/// Returns `true` if the value is a valid W3C `traceparent` header value.
+///
+/// [Trace Context, Section 3.2](https://www.w3.org/TR/trace-context/#traceparent-header)
+#[cfg(feature = "tracing")]
+fn is_valid_traceparent(value: &[u8]) -> bool {
+    fn is_hex(field: &[u8], len: usize) -> bool {
+        field.len() == len
+            && field
+                .iter()
+                .all(|&b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    }
+
+    let mut fields = value.split(|&b| b == b'-');
+    let (Some(version), Some(trace_id), Some(parent_id), Some(flags)) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return false;
+    };
+    // Later versions may append fields, which are ignored.
+    if version == b"00" && fields.next().is_some() {
+        return false;
+    }
+    is_hex(version, 2)
+        && version != b"ff"
+        && is_hex(trace_id, 32)
+        && trace_id.iter().any(|&b| b != b'0')
+        && is_hex(parent_id, 16)
+        && parent_id.iter().any(|&b| b != b'0')
+        && is_hex(flags, 2)
+}
+
+impl TryFrom<Url> for HubUrl {
+    type Error = TryFromUrlError;
+
+    /// Performs the conversion.
+    ///
+    /// The path of the URL must be `/.well-known/mercure`, optionally with a
+    /// trailing slash. Use [`HubUrl::new_unchecked`] for a Mercure hub mounted
+    /// at a different path.
+    fn try_from(url: Url) -> Result<Self, Self::Error> {
+        let hub_url = Self(url);
+        if !hub_url.has_well_known_path() {
+            return Err(TryFromUrlError);
+        }
+
+        Ok(hub_url)
+    }
+}
+
+impl TryFrom<&str> for HubUrl {
+    type Error = ParseHubUrlError;
+
+    /// Performs the conversion.
+    ///
+    /// See [`HubUrl::from_str`].
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for HubUrl {
+    type Err = ParseHubUrlError;
+
+    /// Parses the string as a [`Url`], whose path must be
+    /// `/.well-known/mercure`, optionally with a trailing slash.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::HubUrl;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let hub_url: HubUrl = "https://localhost/.well-known/mercure".parse()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url: Url = s.parse().map_err(|err: url::ParseError| ParseHubUrlError {
+            kind: ParseHubUrlErrorKind::InvalidUrl,
+            inner: err.into(),
+        })?;
+
+        url.try_into()
+            .map_err(|err: TryFromUrlError| ParseHubUrlError {
+                kind: ParseHubUrlErrorKind::InvalidPath,
+                inner: err.into(),
+            })
+    }
+}
+
+impl AsRef<Url> for HubUrl {
+    fn as_ref(&self) -> &Url {
+        &self.0
+    }
+}
+
+impl From<HubUrl> for Url {
+    fn from(hub_url: HubUrl) -> Self {
+        hub_url.0
+    }
+}
+
+impl HubUrl {
+    /// Constructs a `HubUrl` without checking that its path is
+    /// `/.well-known/mercure`.
+    ///
+    /// This is an escape hatch for a Mercure hub mounted at a different path,
+    /// e.g. behind a reverse proxy. Prefer [`HubUrl::try_from`] otherwise.
+    ///
+    /// Deserializing a `HubUrl` always checks its path, but a [`ClientConfig`]
+    /// remembers that its hub URL is unchecked, so it still round-trips.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::HubUrl;
+    /// use url::Url;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let hub_url = HubUrl::new_unchecked("https://localhost/hub".parse::<Url>()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_unchecked(url: Url) -> Self {
+        Self(url)
+    }
+
+    fn has_well_known_path(&self) -> bool {
+        self.0.path().strip_suffix('/').unwrap_or(self.0.path()) == HUB_PATH
+    }
+
+    /// Returns the URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::HubUrl;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let hub_url: HubUrl = "https://example.com/.well-known/mercure".parse()?;
+    /// assert_eq!(hub_url.as_url().host_str(), Some("example.com"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn as_url(&self) -> &Url {
+        &self.0
+    }
+
+    /// Returns the URL, consuming this `HubUrl`.
+    pub fn into_url(self) -> Url {
+        self.0
+    }
+
+    /// Returns a `Link` header value advertising this Mercure hub, for use in
+    /// responses to requests for resources.
+    ///
+    /// [The Mercure Protocol, Section 2](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-2)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::HubUrl;
+    /// use url::Url;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// assert_eq!(
+    ///     hub_url.to_link_header(),
+    ///     r#"<https://localhost/.well-known/mercure>; rel="mercure""#
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_link_header(&self) -> HeaderValue {
+        format!(r#"<{url}>; rel="mercure""#, url = self.0)
+            .parse()
+            .expect("`url` should not contain invalid header characters")
+    }
+
+    /// Returns an error if the URL does not use HTTPS.
+    ///
+    /// Combined with [`HubUrl::is_loopback`], this can be used to enforce a
+    /// policy of using HTTPS everywhere except on the local machine.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::HubUrl;
+    /// use url::Url;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let hub_url = HubUrl::try_from("https://example.com/.well-known/mercure".parse::<Url>()?)?;
+    /// hub_url.require_https()?;
+    ///
+    /// let hub_url = HubUrl::try_from("http://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// if !hub_url.is_loopback() {
+    ///     hub_url.require_https()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn require_https(&self) -> Result<(), RequireHttpsError> {
+        if self.0.scheme() != "https" {
+            return Err(RequireHttpsError);
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the host of the URL is a loopback address (e.g.
+    /// `localhost`).
+    pub fn is_loopback(&self) -> bool {
+        match self.0.host() {
+            Some(Host::Domain(domain)) => domain == "localhost",
+            Some(Host::Ipv4(addr)) => addr.is_loopback(),
+            Some(Host::Ipv6(addr)) => addr.is_loopback(),
+            None => false,
+        }
+    }
+
+    /// Returns `true` if the URL uses HTTPS, or its host is a loopback
+    /// address.
+    fn is_secure(&self) -> bool {
+        self.require_https().is_ok() || self.is_loopback()
+    }
+}
+
+impl fmt::Display for HubUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{url}", url = self.0)
+    }
+}
+
+impl fmt::Display for TryFromUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the path of the URL must be {HUB_PATH}")
+    }
+}
+
+impl Error for TryFromUrlError {}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for TryFromUrlError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("TryFromUrlError", 1)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl fmt::Display for ParseHubUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseHubUrlErrorKind::InvalidUrl => {
+                write!(f, "failed to parse hub URL: {err}", err = self.inner)
+            },
+            ParseHubUrlErrorKind::InvalidPath => {
+                write!(f, "invalid hub URL: {err}", err = self.inner)
+            },
+        }
+    }
+}
+
+impl Error for ParseHubUrlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.inner.as_ref())
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for ParseHubUrlError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ParseHubUrlError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl ParseHubUrlError {
+    /// Returns the corresponding [`ParseHubUrlErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &ParseHubUrlErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for InvalidRevisionIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the revision ID must not start with #")
+    }
+}
+
+impl Error for InvalidRevisionIdError {}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for InvalidRevisionIdError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("InvalidRevisionIdError", 1)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl fmt::Display for RequireHttpsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the scheme of the URL must be https")
+    }
+}
+
+impl Error for RequireHttpsError {}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for RequireHttpsError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("RequireHttpsError", 1)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl PublishUpdatePrivacy {
+    /// Returns `true` if this is [`PublishUpdatePrivacy::Public`].
+    ///
+    /// This is meant for `#[serde(skip_serializing_if = "...")]`, which only
+    /// accepts a path.
+    ///
+    /// <https://github.com/serde-rs/serde/blob/v1.0.219/serde_derive/src/internals/attr.rs#L988>
+    pub(crate) fn is_public(&self) -> bool {
+        *self == Self::Public
+    }
+}
+
+impl<'de> Deserialize<'de> for PublishUpdatePrivacy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct PrivacyVisitor;
+
+        impl serde::de::Visitor<'_> for PrivacyVisitor {
+            type Value = PublishUpdatePrivacy;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, r#""on" or a boolean"#)
+            }
+
+            fn visit_bool<E>(self, is_private: bool) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(is_private.into())
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if value == "on" {
+                    Ok(PublishUpdatePrivacy::Private)
+                } else {
+                    Err(E::invalid_value(serde::de::Unexpected::Str(value), &self))
+                }
+            }
+
+            fn visit_none<E>(self) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(PublishUpdatePrivacy::Public)
+            }
+
+            fn visit_unit<E>(self) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(PublishUpdatePrivacy::Public)
+            }
+        }
+
+        deserializer.deserialize_any(PrivacyVisitor)
+    }
+}
+
+impl From<bool> for PublishUpdatePrivacy {
+    /// Converts `true` to [`PublishUpdatePrivacy::Private`], and `false` to
+    /// [`PublishUpdatePrivacy::Public`].
+    fn from(is_private: bool) -> Self {
+        if is_private {
+            Self::Private
+        } else {
+            Self::Public
+        }
+    }
+}
+
+impl From<PublishUpdatePrivacy> for bool {
+    /// Converts [`PublishUpdatePrivacy::Private`] to `true`, and
+    /// [`PublishUpdatePrivacy::Public`] to `false`.
+    fn from(privacy: PublishUpdatePrivacy) -> Self {
+        privacy == PublishUpdatePrivacy::Private
+    }
+}
+
+impl TryFrom<String> for RevisionId {
+    type Error = InvalidRevisionIdError;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        if id.starts_with('#') {
+            return Err(InvalidRevisionIdError);
+        }
+
+        Ok(Self(id))
+    }
+}
+
+impl fmt::Display for RevisionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{rev}", rev = self.0)
+    }
+}
+
+impl AsRef<str> for RevisionId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for RevisionId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for RevisionId {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<RevisionId> for str {
+    fn eq(&self, other: &RevisionId) -> bool {
+        self == other.0
+    }
+}
+
+impl PartialEq<RevisionId> for &str {
+    fn eq(&self, other: &RevisionId) -> bool {
+        *self == other.0
+    }
+}
+
+impl RevisionId {
+    /// Returns the revision ID as a string slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::client::RevisionId;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let rev = RevisionId::try_from("urn:uuid:1".to_owned())?;
+    /// assert_eq!(rev.as_str(), "urn:uuid:1");
+    /// assert_eq!(rev, "urn:uuid:1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PublishUpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PublishUpdateErrorKind::Unauthorized => {
+                write!(
+                    f,
+                    "publisher JWT does not authorize publishing to topic {url}",
+                    url = self.inner
+                )
+            },
+            PublishUpdateErrorKind::InsecureHubUrl => {
+                write!(f, "refused to send publisher JWT to Mercure hub over HTTP")
+            },
+            PublishUpdateErrorKind::SerializeParameters => {
+                let err = self
+                    .inner
+                    .downcast_ref::<serde_html_form::ser::Error>()
+                    .unwrap();
+                write!(
+                    f,
+                    "failed to serialize parameters to application/x-www-form-urlencoded: {err}"
+                )
+            },
+            PublishUpdateErrorKind::SendRequest => {
+                write!(
+                    f,
+                    "failed to send request to Mercure hub: {err}",
+                    err = self.inner
+                )
+            },
+            PublishUpdateErrorKind::ReadResponse => {
+                write!(
+                    f,
+                    "failed to read response from Mercure hub: {err}",
+                    err = self.inner
+                )
+            },
+            PublishUpdateErrorKind::UnexpectedStatus { status, .. } => {
+                write!(
+                    f,
+                    "Mercure hub responded with an unexpected status: {status}"
+                )
+            },
+            PublishUpdateErrorKind::PublisherShutDown => {
+                write!(f, "publisher has been shut down")
+            },
+            PublishUpdateErrorKind::Cancelled => write!(f, "publishing was cancelled"),
+        }
+    }
+}
+
+impl Error for PublishUpdateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            PublishUpdateErrorKind::Unauthorized
+            | PublishUpdateErrorKind::InsecureHubUrl
+            | PublishUpdateErrorKind::UnexpectedStatus { .. }
+            | PublishUpdateErrorKind::PublisherShutDown
+            | PublishUpdateErrorKind::Cancelled => None,
+            PublishUpdateErrorKind::SerializeParameters => {
+                let err = self
+                    .inner
+                    .downcast_ref::<serde_html_form::ser::Error>()
+                    .unwrap();
+                Some(err)
+            },
+            PublishUpdateErrorKind::SendRequest | PublishUpdateErrorKind::ReadResponse => {
+                Some(self.inner.as_ref())
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for PublishUpdateError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("PublishUpdateError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl PublishUpdateError {
+    /// Returns the corresponding [`PublishUpdateErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &PublishUpdateErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for SubscribeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            SubscribeErrorKind::InvalidLastEventId => {
+                let err = self
+                    .inner
+                    .downcast_ref::<header::InvalidHeaderValue>()
+                    .unwrap();
+                write!(f, "invalid last event ID: {err}")
+            },
+            SubscribeErrorKind::ExtraLastEventId => {
+                write!(
+                    f,
+                    "last event ID must be set with `SubscriptionBuilder::last_event_id`, not as \
+                     an extra {extra}",
+                    extra = self.inner
+                )
+            },
+            SubscribeErrorKind::SendRequest => {
+                write!(
+                    f,
+                    "failed to send request to Mercure hub: {err}",
+                    err = self.inner
+                )
+            },
+            SubscribeErrorKind::ErrorStatus => {
+                write!(
+                    f,
+                    "Mercure hub responded with an error status: {status}",
+                    status = self.inner
+                )
+            },
+            SubscribeErrorKind::ConnectTimeout => {
+                write!(
+                    f,
+                    "timed out connecting to Mercure hub after {connect_timeout}",
+                    connect_timeout = self.inner
+                )
+            },
+            SubscribeErrorKind::Cancelled => write!(f, "subscribing was cancelled"),
+        }
+    }
+}
+
+impl Error for SubscribeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            SubscribeErrorKind::InvalidLastEventId => {
+                let err = self
+                    .inner
+                    .downcast_ref::<header::InvalidHeaderValue>()
+                    .unwrap();
+                Some(err)
+            },
+            SubscribeErrorKind::SendRequest => Some(self.inner.as_ref()),
+            SubscribeErrorKind::ExtraLastEventId
+            | SubscribeErrorKind::ConnectTimeout
+            | SubscribeErrorKind::ErrorStatus
+            | SubscribeErrorKind::Cancelled => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for SubscribeError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("SubscribeError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl SubscribeError {
+    /// Returns the corresponding [`SubscribeErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &SubscribeErrorKind {
+        &self.kind
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use anyhow::{Context as _, Result};
+
+    use super::*;
+
+    #[test]
+    fn it_redacts_publisher_jwt_in_debug_output() -> Result<()> {
+        let publisher_jwt = test_publisher_jwt()?;
+        let client = test_client(UNUSED_HUB_ADDR)?;
+        let debug_output = format!("{client:?}");
+        assert!(!debug_output.contains(&publisher_jwt.to_string()));
+        assert!(debug_output.contains("[REDACTED]"));
+        assert_eq!(
+            client.authorization_header(),
+            format!("Bearer {publisher_jwt}").as_str()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refuses_to_publish_to_insecure_hub_url() -> Result<()> {
+        let hub_url = HubUrl::try_from("http://example.com/.well-known/mercure".parse::<Url>()?)?;
+        let client = Client::new(reqwest::Client::new(), hub_url, test_publisher_jwt()?);
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        let err = client
+            .publish_update(topic, None, PublishUpdatePrivacy::Public)
+            .await
+            .expect_err("publishing to an insecure hub URL should fail");
+        assert!(matches!(err.kind(), PublishUpdateErrorKind::InsecureHubUrl));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refuses_to_publish_delete_to_unauthorized_topic() -> Result<()> {
+        let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+        let publisher_jwt = PublisherJwt::new(
+            &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+            vec![crate::TopicSelector::UriTemplate(
+                "https://example.com/books/{book_id}".try_into()?,
+            )],
+        )?;
+        let client = Client::new(reqwest::Client::new(), hub_url, publisher_jwt);
+        let topic = Topic::new(
+            "https://example.com/books/1".parse()?,
+            vec!["https://example.com/users/1/books/1".parse()?],
+        );
+        let err = client
+            .publish_delete(topic, PublishUpdatePrivacy::Private)
+            .await
+            .expect_err("publishing to an unauthorized topic should fail");
+        assert!(matches!(err.kind(), PublishUpdateErrorKind::Unauthorized));
+        assert_eq!(
+            err.to_string(),
+            "publisher JWT does not authorize publishing to topic https://example.com/users/1/books/1"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_checks_topic_authorization_before_publishing() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+        ])
+        .await?;
+        let hub_url =
+            HubUrl::try_from(format!("http://{addr}/.well-known/mercure").parse::<Url>()?)?;
+        let publisher_jwt = PublisherJwt::new(
+            &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+            vec![crate::TopicSelector::UriTemplate(
+                "https://example.com/books/{book_id}".try_into()?,
+            )],
+        )?;
+        let client = Client::builder(hub_url, publisher_jwt)
+            .check_topic_authorization(true)
+            .build();
+        assert!(client.config().check_topic_authorization());
+
+        let topic = Topic::new("https://example.com/users/1".parse()?, vec![]);
+        let err = client
+            .publish_update(topic.clone(), Some("Hello!"), PublishUpdatePrivacy::Public)
+            .await
+            .expect_err("publishing to an unauthorized topic should fail");
+        assert!(matches!(err.kind(), PublishUpdateErrorKind::Unauthorized));
+        assert_eq!(
+            err.to_string(),
+            "publisher JWT does not authorize publishing to topic https://example.com/users/1"
+        );
+        let err = client
+            .prepare_publish(topic, PublishUpdatePrivacy::Public)
+            .err()
+            .context("preparing to publish to an unauthorized topic should fail")?;
+        assert!(matches!(err.kind(), PublishUpdateErrorKind::Unauthorized));
+
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        let rev = client
+            .publish_update(topic, Some("Hello!"), PublishUpdatePrivacy::Public)
+            .await?;
+        assert_eq!(rev, "urn:uuid:1");
+        server.await??;
+        Ok(())
+    }
+
+    #[cfg(feature = "serde-errors")]
+    #[tokio::test]
+    async fn it_serializes_publish_update_error() -> Result<()> {
+        let hub_url = HubUrl::try_from("http://example.com/.well-known/mercure".parse::<Url>()?)?;
+        let client = Client::new(reqwest::Client::new(), hub_url, test_publisher_jwt()?);
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        let err = client
+            .publish_update(topic, None, PublishUpdatePrivacy::Public)
+            .await
+            .expect_err("publishing to an insecure hub URL should fail");
+        assert_eq!(
+            serde_json::to_string(&err)?,
+            r#"{"kind":"InsecureHubUrl","message":"refused to send publisher JWT to Mercure hub over HTTP"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_treats_loopback_hub_url_as_secure() -> Result<()> {
+        for url in [
+            "https://example.com/.well-known/mercure",
+            "http://localhost/.well-known/mercure",
+            "http://127.0.0.1:3000/.well-known/mercure",
+            "http://[::1]/.well-known/mercure",
+        ] {
+            assert!(HubUrl::try_from(url.parse::<Url>()?)?.is_secure(), "{url}");
+        }
+        assert!(
+            !HubUrl::try_from("http://example.com/.well-known/mercure".parse::<Url>()?)?
+                .is_secure()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_serializes_client_config_without_publisher_jwt() -> Result<()> {
+        let client = test_client(UNUSED_HUB_ADDR)?;
+        let client_config = serde_json::to_string(&client.config())?;
+        assert_eq!(
+            client_config,
+            r#"{"hub_url":"http://127.0.0.1:0/.well-known/mercure","accept_insecure_hub_url":false}"#
+        );
+        let client = Client::from_config(
+            reqwest::Client::new(),
+            test_publisher_jwt()?,
+            serde_json::from_str(&client_config)?,
+        );
+        assert_eq!(serde_json::to_string(&client.config())?, client_config);
+        Ok(())
+    }
+
+    #[test]
+    fn it_round_trips_unchecked_hub_url_through_client_config() -> Result<()> {
+        let hub_url = HubUrl::new_unchecked("https://localhost/hub".parse::<Url>()?);
+        let publisher_jwt = test_publisher_jwt()?;
+        let client = Client::new(reqwest::Client::new(), hub_url.clone(), publisher_jwt);
+        let client_config = serde_json::to_string(&client.config())?;
+        assert_eq!(
+            client_config,
+            r#"{"hub_url":"https://localhost/hub","hub_url_unchecked":true,"accept_insecure_hub_url":false}"#
+        );
+        let deserialized: ClientConfig = serde_json::from_str(&client_config)?;
+        assert_eq!(deserialized.hub_url(), &hub_url);
+
+        assert!(serde_json::from_str::<ClientConfig>(
+            r#"{"hub_url":"https://localhost/hub","accept_insecure_hub_url":false}"#
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_serializes_privacy_if_private() -> Result<()> {
+        let canonical_url = "https://example.com/books/1".parse()?;
+        let alternate_urls = vec!["https://example.com/users/1/books/1".parse()?];
+        let params = PublishUpdateParams {
+            topic: Cow::Owned(Topic::new(canonical_url, alternate_urls)),
+            data: None,
+            privacy: PublishUpdatePrivacy::Private,
+            id: None,
+            r#type: None,
+            retry: None,
+        };
+        let encoded_params = serde_html_form::to_string(params)
+            .context("Failed to serialize parameters to application/x-www-form-urlencoded")?;
+        assert_eq!(
+            encoded_params,
+            "topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&topic=https%3A%2F%2Fexample.com%2Fusers%\
+             2F1%2Fbooks%2F1&private=on"
+        );
+        Ok(())
+    }
+
+    /// Serves each of the given responses on a new connection, returning the
+    /// received requests.
+    async fn serve(
+        responses: Vec<&'static [u8]>,
+    ) -> Result<(
+        std::net::SocketAddr,
+        tokio::task::JoinHandle<Result<Vec<String>>>,
+    )> {
+        use tokio::io::AsyncWriteExt as _;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let mut reqs = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await?;
+                reqs.push(read_request(&mut stream).await?);
+                stream.write_all(response).await?;
+            }
+            Ok(reqs)
+        });
+        Ok((addr, server))
+    }
+
+    /// Reads a request up to the end of its headers.
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> Result<String> {
+        use tokio::io::AsyncReadExt as _;
+
+        let mut req = Vec::new();
+        while !req.windows(4).any(|window| window == b"\r\n\r\n") {
+            let mut buf = [0; 1024];
+            let n = stream.read(&mut buf).await?;
+            req.extend_from_slice(&buf[..n]);
+        }
+        Ok(String::from_utf8(req)?)
+    }
+
+    /// The address of a hub which is never connected to, for tests which do
+    /// not send any request.
+    pub(crate) const UNUSED_HUB_ADDR: std::net::SocketAddr = std::net::SocketAddr::V4(
+        std::net::SocketAddrV4::new(std::net::Ipv4Addr::LOCALHOST, 0),
+    );
+
+    /// Constructs the publisher JWT used by test clients.
+    pub(crate) fn test_publisher_jwt() -> Result<PublisherJwt> {
+        Ok(PublisherJwt::new(
+            &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+            vec![TopicSelector::Wildcard],
+        )?)
+    }
+
+    /// Constructs a client builder for the hub served on the given address.
+    pub(crate) fn test_client_builder(addr: std::net::SocketAddr) -> Result<ClientBuilder> {
+        let hub_url =
+            HubUrl::try_from(format!("http://{addr}/.well-known/mercure").parse::<Url>()?)?;
+        Ok(Client::builder(hub_url, test_publisher_jwt()?))
+    }
+
+    /// Constructs a client for the hub served on the given address.
+    pub(crate) fn test_client(addr: std::net::SocketAddr) -> Result<Client> {
+        Ok(test_client_builder(addr)?.build())
+    }
+
+    #[tokio::test]
+    async fn it_subscribes_to_updates() -> Result<()> {
+        use futures_util::TryStreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n:\n\nid: urn:uuid:1\ndata: \
+              {\ndata: }\n\nid: urn:uuid:2\nevent: BookDeleted\ndata:\n\nid: urn:uuid:3\ndata: \
+              partial\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let updates: Vec<_> = client
+            .subscribe(
+                vec![
+                    TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?),
+                    TopicSelector::Wildcard,
+                ],
+                Some("urn:uuid:0"),
+            )
+            .await?
+            .auto_reconnect(false)
+            .try_collect()
+            .await?;
+
+        let reqs = server.await??;
+        assert!(reqs[0].starts_with(
+            "GET /.well-known/mercure?topic=https%3A%2F%2Fexample.com%2Fbooks%2F%7Bbook_id%7D&topic=*"
+        ));
+        assert!(reqs[0].contains("accept: text/event-stream\r\n"));
+        assert!(reqs[0].contains("cache-control: no-cache\r\n"));
+        assert!(reqs[0].contains("last-event-id: urn:uuid:0\r\n"));
+        assert_eq!(
+            updates
+                .iter()
+                .map(|update| (update.id(), update.event_type(), update.data()))
+                .collect::<Vec<_>>(),
+            [
+                (Some("urn:uuid:1"), "message", "{\n}"),
+                (Some("urn:uuid:2"), "BookDeleted", ""),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_routes_updates_by_type() -> Result<()> {
+        use std::sync::{Arc, Mutex};
+
+        use crate::update::UpdateRouter;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nid: urn:uuid:1\ndata: \
+              1\n\nid: urn:uuid:2\nevent: BookDeleted\ndata: 2\n\nid: urn:uuid:3\nevent: \
+              BookArchived\ndata: 3\n\nid: urn:uuid:4\nevent: message\ndata: 4\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let update_stream = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .auto_reconnect(false);
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let handler = |name: &'static str| {
+            let handled = Arc::clone(&handled);
+            move |update: crate::update::Update| {
+                let handled = Arc::clone(&handled);
+                async move {
+                    handled
+                        .lock()
+                        .unwrap()
+                        .push((name, update.data().to_owned()));
+                }
+            }
+        };
+        UpdateRouter::new()
+            .message(handler("message"))
+            .route("BookDeleted", handler("BookDeleted"))
+            .fallback(handler("fallback"))
+            .run(update_stream)
+            .await?;
+
+        server.await??;
+        assert_eq!(*handled.lock().unwrap(), [
+            ("message", "1".to_owned()),
+            ("BookDeleted", "2".to_owned()),
+            ("fallback", "3".to_owned()),
+            ("message", "4".to_owned()),
+        ]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_subscribes_with_extra_query_params_and_headers() -> Result<()> {
+        use futures_util::TryStreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nid: urn:uuid:1\ndata: \
+              {}\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let updates: Vec<_> = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .last_event_id("urn:uuid:0")
+            .query_param("lang", "en")
+            .query_param("topic", "https://example.com/books/1")
+            .header(
+                header::ACCEPT,
+                HeaderValue::from_static("text/event-stream; q=1"),
+            )
+            .header(header::CACHE_CONTROL, HeaderValue::from_static("max-age=0"))
+            .header(
+                HeaderName::from_static("x-request-id"),
+                HeaderValue::from_static("42"),
+            )
+            .subscribe()
+            .await?
+            .auto_reconnect(false)
+            .try_collect()
+            .await?;
+        assert_eq!(updates.len(), 1);
+
+        let reqs = server.await??;
+        assert!(reqs[0].starts_with(
+            "GET /.well-known/mercure?topic=*&lang=en&topic=https%3A%2F%2Fexample.com%2Fbooks%2F1 "
+        ));
+        assert!(reqs[0].contains("accept: text/event-stream; q=1\r\n"));
+        assert!(!reqs[0].contains("accept: text/event-stream\r\n"));
+        assert!(reqs[0].contains("cache-control: max-age=0\r\n"));
+        assert!(!reqs[0].contains("cache-control: no-cache\r\n"));
+        assert!(reqs[0].contains("last-event-id: urn:uuid:0\r\n"));
+        assert!(reqs[0].contains("x-request-id: 42\r\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_refuses_to_subscribe_with_extra_last_event_id() -> Result<()> {
+        let client = test_client(UNUSED_HUB_ADDR)?;
+        let err = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .query_param("lastEventID", "urn:uuid:0")
+            .subscribe()
+            .await
+            .err()
+            .context("subscribing with lastEventID query parameter should fail")?;
+        assert!(matches!(err.kind(), SubscribeErrorKind::ExtraLastEventId));
+        let err = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .header(
+                HeaderName::from_static("last-event-id"),
+                HeaderValue::from_static("urn:uuid:0"),
+            )
+            .subscribe()
+            .await
+            .err()
+            .context("subscribing with Last-Event-ID header should fail")?;
+        assert!(matches!(err.kind(), SubscribeErrorKind::ExtraLastEventId));
+        assert_eq!(
+            err.to_string(),
+            "last event ID must be set with `SubscriptionBuilder::last_event_id`, not as an \
+             extra `Last-Event-ID` header"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reconnects_with_extras_and_received_last_event_id() -> Result<()> {
+        use futures_util::StreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nretry: 10\nid: \
+              urn:uuid:1\ndata: 1\n\n",
+            b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let mut update_stream = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .last_event_id("urn:uuid:0")
+            .query_param("lang", "en")
+            .reconnect_policy(ReconnectPolicy::new(
+                ReconnectPolicy::MIN_DELAY,
+                ReconnectPolicy::MIN_DELAY,
+            )?)
+            .header(
+                HeaderName::from_static("x-request-id"),
+                HeaderValue::from_static("42"),
+            )
+            .subscribe()
+            .await?;
+        let update = update_stream
+            .next()
+            .await
+            .context("update should be received")??;
+        assert_eq!(update.data(), "1");
+        update_stream
+            .next()
+            .await
+            .context("error should be returned")?
+            .expect_err("reconnection should fail with an error status");
+
+        let reqs = server.await??;
+        assert_eq!(reqs.len(), 2);
+        assert!(reqs[1].starts_with("GET /.well-known/mercure?topic=*&lang=en "));
+        assert!(reqs[1].contains("last-event-id: urn:uuid:1\r\n"));
+        assert!(!reqs[1].contains("urn:uuid:0"));
+        assert!(reqs[1].contains("cache-control: no-cache\r\n"));
+        assert!(reqs[1].contains("x-request-id: 42\r\n"));
+        Ok(())
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn it_subscribes_to_compressed_updates() -> Result<()> {
+        use futures_util::TryStreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Encoding: \
+              gzip\r\nContent-Length: 45\r\n\r\n\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\
+              \xcb\x4c\xb1\x52\x28\x2d\xca\xb3\x2a\x2d\xcd\x4c\xb1\x32\xe4\x4a\x49\x2c\x49\xb4\
+              \x52\xa8\xae\xe5\xe2\x02\x00\xb9\x3f\xcb\x4e\x19\x00\x00\x00",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nid: urn:uuid:2\ndata: \
+              {}\n\n",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nid: urn:uuid:3\ndata: \
+              {}\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let updates: Vec<_> = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .auto_reconnect(false)
+            .try_collect()
+            .await?;
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].id(), Some("urn:uuid:1"));
+        assert_eq!(updates[0].data(), "{}");
+        let updates: Vec<_> = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .compression(false)
+            .subscribe()
+            .await?
+            .auto_reconnect(false)
+            .try_collect()
+            .await?;
+        assert_eq!(updates.len(), 1);
+        let http_client = reqwest::Client::builder().no_gzip().no_deflate().build()?;
+        let client = test_client_builder(addr)?.http_client(http_client).build();
+        let updates: Vec<_> = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .auto_reconnect(false)
+            .try_collect()
+            .await?;
+        assert_eq!(updates.len(), 1);
+
+        let reqs = server.await??;
+        assert!(reqs[0].contains("accept-encoding: gzip,deflate\r\n"));
+        assert!(reqs[1].contains("accept-encoding: identity\r\n"));
+        assert!(!reqs[2].contains("accept-encoding"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reconnects_with_last_event_id() -> Result<()> {
+        use futures_util::StreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nretry: 10\nid: \
+              urn:uuid:1\ndata: 1\n\n",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nid: urn:uuid:2\ndata: \
+              2\n\n",
+            b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let mut update_stream = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?;
+        assert_eq!(update_stream.last_event_id(), None);
+        let update = update_stream
+            .next()
+            .await
+            .context("update should be received")??;
+        assert_eq!(update.data(), "1");
+        let update = update_stream
+            .next()
+            .await
+            .context("update should be received")??;
+        assert_eq!(update.data(), "2");
+        assert_eq!(update_stream.last_event_id(), Some("urn:uuid:2"));
+        let err = update_stream
+            .next()
+            .await
+            .context("error should be returned")?
+            .expect_err("reconnection should fail with an error status");
+        assert!(matches!(
+            err.kind(),
+            crate::update::StreamErrorKind::ErrorStatus
+        ));
+        assert!(update_stream.next().await.is_none());
+
+        let reqs = server.await??;
+        assert!(!reqs[0].contains("last-event-id"));
+        assert!(reqs[1].contains("last-event-id: urn:uuid:1\r\n"));
+        assert!(reqs[2].contains("last-event-id: urn:uuid:2\r\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_cancels_subscription() -> Result<()> {
+        // Keeps the connection open after the first update.
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nid: urn:uuid:1\ndata: \
+              first\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+        let mut update_stream = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .cancel_on(async move {
+                let _ = cancel_rx.await;
+            })
+            .subscribe()
+            .await?;
+
+        let update = update_stream
+            .next()
+            .await
+            .context("stream should yield the first update")??;
+        assert_eq!(update.data(), "first");
+        cancel_tx
+            .send(())
+            .ok()
+            .context("cancellation signal should be received")?;
+        assert!(update_stream.next().await.is_none());
+        assert!(update_stream.next().await.is_none());
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_cancels_subscribing() -> Result<()> {
+        // Never accepts the connection, so the request stays in flight.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        let client = test_client(addr)?;
+        let err = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .cancel_on(tokio::time::sleep(Duration::from_millis(10)))
+            .subscribe()
+            .await
+            .err()
+            .context("subscribing should be cancelled")?;
+        assert!(matches!(err.kind(), SubscribeErrorKind::Cancelled));
+        assert_eq!(err.to_string(), "subscribing was cancelled");
+        drop(listener);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_times_out_connecting_to_subscribe() -> Result<()> {
+        // Never accepts the connection, so no response is received.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        let client = test_client(addr)?;
+        let err = client
+            .subscription(vec![TopicSelector::Wildcard])
+            .connect_timeout(Duration::from_millis(10))
+            .subscribe()
+            .await
+            .err()
+            .context("subscribing should time out")?;
+        assert!(matches!(err.kind(), SubscribeErrorKind::ConnectTimeout));
+        assert_eq!(
+            err.to_string(),
+            "timed out connecting to Mercure hub after 10ms"
+        );
+        drop(listener);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_limits_replayed_updates() -> Result<()> {
+        use futures_util::TryStreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nLast-Event-ID: \
+              urn:uuid:3\r\n\r\nid: urn:uuid:1\ndata: 1\n\nid: urn:uuid:2\ndata: 2\n\nid: \
+              urn:uuid:3\ndata: 3\n\n",
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nLast-Event-ID: \
+              urn:uuid:3\r\n\r\nid: urn:uuid:1\ndata: 1\n\nid: urn:uuid:2\ndata: 2\n\nid: \
+              urn:uuid:3\ndata: 3\n\nid: urn:uuid:4\ndata: 4\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let update_stream = client
+            .subscribe(vec![TopicSelector::Wildcard], Some("urn:uuid:0"))
+            .await?
+            .replay_limit(2);
+        let updates: Vec<_> = update_stream.try_collect().await?;
+        assert_eq!(
+            updates
+                .iter()
+                .map(crate::update::Update::data)
+                .collect::<Vec<_>>(),
+            ["1", "2"]
+        );
+
+        // The limit no longer applies once live.
+        let updates: Vec<_> = client
+            .subscribe(vec![TopicSelector::Wildcard], Some("urn:uuid:0"))
+            .await?
+            .replay_limit(3)
+            .auto_reconnect(false)
+            .try_collect()
+            .await?;
+        assert_eq!(
+            updates
+                .iter()
+                .map(crate::update::Update::data)
+                .collect::<Vec<_>>(),
+            ["1", "2", "3", "4"]
+        );
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_decodes_updates() -> Result<()> {
+        use futures_util::StreamExt as _;
+
+        use crate::update::DecodeUpdateErrorKind;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nretry: 10\ndata: \
+              1\n\ndata: x\n\n",
+            b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let results: Vec<_> = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .decode_with(|data| data.parse::<u64>())
+            .collect()
+            .await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().ok(), Some(&1));
+        let err = results[1].as_ref().err().context("decoding should fail")?;
+        assert!(matches!(err.kind(), DecodeUpdateErrorKind::Decode));
+        assert_eq!(
+            err.to_string(),
+            "failed to decode update: invalid digit found in string"
+        );
+        let err = results[2]
+            .as_ref()
+            .err()
+            .context("reconnection should fail with an error status")?;
+        assert!(matches!(err.kind(), DecodeUpdateErrorKind::Stream));
+        assert!(err
+            .source()
+            .and_then(|err| err.downcast_ref::<crate::update::StreamError>())
+            .is_some());
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_pauses_and_resumes_subscription() -> Result<()> {
+        use futures_util::StreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\ndata: 1\n\ndata: \
+              2\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let mut update_stream = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .auto_reconnect(false);
+        let update = update_stream
+            .next()
+            .await
+            .context("first update should be received")??;
+        assert_eq!(update.data(), "1");
+
+        let pause_handle = update_stream.pause_handle();
+        pause_handle.pause();
+        assert!(update_stream.is_paused());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), update_stream.next())
+                .await
+                .is_err(),
+            "paused stream should not yield updates"
+        );
+
+        let next = tokio::spawn(async move { update_stream.next().await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!next.is_finished());
+        pause_handle.resume();
+        let update = next.await?.context("second update should be received")??;
+        assert_eq!(update.data(), "2");
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_takes_updates_until_predicate_matches() -> Result<()> {
+        use futures_util::TryStreamExt as _;
+
+        // Would reconnect after the connection is closed, without the predicate.
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\ndata: 1\n\nevent: \
+              JobCompleted\ndata: 2\n\ndata: 3\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let updates: Vec<_> = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .take_until(|update| update.event_type() == "JobCompleted")
+            .try_collect()
+            .await?;
+        assert_eq!(
+            updates
+                .iter()
+                .map(crate::update::Update::data)
+                .collect::<Vec<_>>(),
+            ["1", "2"]
+        );
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_calls_on_event_id_callback() -> Result<()> {
+        use std::sync::{Arc, Mutex};
+
+        use futures_util::TryStreamExt as _;
+
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: \
+              close\r\n\r\nid: urn:uuid:1\ndata: 1\n\ndata: 2\n\nid: urn:uuid:3\ndata: 3\n\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let event_ids = Arc::new(Mutex::new(Vec::new()));
+        let updates: Vec<_> = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .auto_reconnect(false)
+            .on_event_id({
+                let event_ids = Arc::clone(&event_ids);
+                move |id| event_ids.lock().unwrap().push(id.to_owned())
+            })
+            .try_collect()
+            .await?;
+        assert_eq!(updates.len(), 3);
+        assert_eq!(*event_ids.lock().unwrap(), vec!["urn:uuid:1", "urn:uuid:3"]);
+
+        server.await??;
+        Ok(())
+    }
+
+    #[test]
+    fn it_redacts_publisher_jwt_in_curl_command() -> Result<()> {
+        let publisher_jwt = test_publisher_jwt()?;
+        let client = test_client(UNUSED_HUB_ADDR)?;
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        let curl_command = client.publish_update_as_curl(
+            topic.clone(),
+            Some("it's"),
+            PublishUpdatePrivacy::Private,
+        )?;
+        assert!(!curl_command.contains(publisher_jwt.as_str()));
+        assert!(curl_command.ends_with(
+            "--data-raw 'topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&data=it%27s&private=on'"
+        ));
+        let curl_command = client.publish_update_as_curl_with_publisher_jwt(
+            topic,
+            None,
+            PublishUpdatePrivacy::Public,
+        )?;
+        assert!(curl_command.contains(&format!("'Authorization: Bearer {publisher_jwt}'")));
+        Ok(())
+    }
+
+    #[test]
+    fn it_uses_authorization_cookie_in_curl_command() -> Result<()> {
+        let publisher_jwt = test_publisher_jwt()?;
+        let client = test_client_builder(UNUSED_HUB_ADDR)?
+            .authorization_mode(AuthorizationMode::Cookie)
+            .build();
+        let curl_command = client.publish_update_as_curl_with_publisher_jwt(
+            Topic::new("https://example.com/books/1".parse()?, vec![]),
+            None,
+            PublishUpdatePrivacy::Public,
+        )?;
+        assert!(curl_command.contains(&format!("'Cookie: mercureAuthorization={publisher_jwt}'")));
+        assert!(!curl_command.contains("Authorization: Bearer"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_privacy_from_and_to_bool() -> Result<()> {
+        assert_eq!(
+            PublishUpdatePrivacy::from(true),
+            PublishUpdatePrivacy::Private
+        );
+        assert_eq!(
+            PublishUpdatePrivacy::from(false),
+            PublishUpdatePrivacy::Public
+        );
+        assert!(bool::from(PublishUpdatePrivacy::Private));
+        assert!(!bool::from(PublishUpdatePrivacy::Public));
+        Ok(())
     }
-}
 
-impl Error for TryFromUrlError {}
+    #[test]
+    fn it_deserializes_privacy() -> Result<()> {
+        #[derive(Debug, Deserialize, Serialize)]
+        struct Envelope {
+            #[serde(default, skip_serializing_if = "PublishUpdatePrivacy::is_public")]
+            private: PublishUpdatePrivacy,
+        }
 
-impl PublishUpdatePrivacy {
-    /// `#[serde(skip_serializing_if = "...")]` only accepts a path.
-    ///
-    /// <https://github.com/serde-rs/serde/blob/v1.0.219/serde_derive/src/internals/attr.rs#L988>
-    fn is_public(&self) -> bool {
-        *self == Self::Public
-    }
-}
+        assert_eq!(
+            serde_json::from_str::<PublishUpdatePrivacy>(r#""on""#)?,
+            PublishUpdatePrivacy::Private
+        );
+        assert_eq!(
+            serde_json::from_str::<PublishUpdatePrivacy>("true")?,
+            PublishUpdatePrivacy::Private
+        );
+        assert_eq!(
+            serde_json::from_str::<PublishUpdatePrivacy>("false")?,
+            PublishUpdatePrivacy::Public
+        );
+        assert_eq!(
+            serde_json::from_str::<PublishUpdatePrivacy>("null")?,
+            PublishUpdatePrivacy::Public
+        );
+        assert!(serde_json::from_str::<PublishUpdatePrivacy>(r#""off""#).is_err());
 
-impl fmt::Display for RevisionId {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{rev}", rev = self.0)
+        for privacy in [PublishUpdatePrivacy::Public, PublishUpdatePrivacy::Private] {
+            let envelope = serde_json::to_string(&Envelope { private: privacy })?;
+            assert_eq!(
+                serde_json::from_str::<Envelope>(&envelope)?.private,
+                privacy
+            );
+        }
+        Ok(())
     }
-}
 
-impl fmt::Display for PublishUpdateError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.kind {
-            PublishUpdateErrorKind::SerializeParameters => {
-                let err = self
-                    .inner
-                    .downcast_ref::<serde_html_form::ser::Error>()
-                    .unwrap();
-                write!(
-                    f,
-                    "failed to serialize parameters to application/x-www-form-urlencoded: {err}"
-                )
-            },
-            PublishUpdateErrorKind::SendRequest => {
-                let err = self.inner.downcast_ref::<reqwest::Error>().unwrap();
-                write!(f, "failed to send request to Mercure hub: {err}")
-            },
-            PublishUpdateErrorKind::ReadResponse => {
-                let err = self.inner.downcast_ref::<reqwest::Error>().unwrap();
-                write!(f, "failed to read response from Mercure hub: {err}")
-            },
+    #[test]
+    fn it_substitutes_data_in_prepared_publish() -> Result<()> {
+        let client = test_client(UNUSED_HUB_ADDR)?;
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        let prepared_publish =
+            client.prepare_publish(topic.clone(), PublishUpdatePrivacy::Private)?;
+        for data in [r#"{"isbn":"9780735218789"}"#, "a&b=c d+é", ""] {
+            let mut expected: Vec<(String, String)> =
+                serde_html_form::from_str(&serde_html_form::to_string(PublishUpdateParams {
+                    topic: Cow::Borrowed(&topic),
+                    data: Some(data.into()),
+                    privacy: PublishUpdatePrivacy::Private,
+                    id: None,
+                    r#type: None,
+                    retry: None,
+                })?)?;
+            let mut actual: Vec<(String, String)> =
+                serde_html_form::from_str(&prepared_publish.body(data))?;
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected);
         }
+        assert_eq!(
+            prepared_publish.body("a&b"),
+            "topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&private=on&data=a%26b"
+        );
+        Ok(())
     }
-}
 
-impl Error for PublishUpdateError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        match self.kind {
-            PublishUpdateErrorKind::SerializeParameters => {
-                let err = self
-                    .inner
-                    .downcast_ref::<serde_html_form::ser::Error>()
-                    .unwrap();
-                Some(err)
-            },
-            PublishUpdateErrorKind::SendRequest => {
-                let err = self.inner.downcast_ref::<reqwest::Error>().unwrap();
-                Some(err)
-            },
-            PublishUpdateErrorKind::ReadResponse => {
-                let err = self.inner.downcast_ref::<reqwest::Error>().unwrap();
-                Some(err)
-            },
-        }
+    #[test]
+    fn it_serializes_id_if_provided() -> Result<()> {
+        let canonical_url = "https://example.com/books/1".parse()?;
+        let alternate_urls = vec![];
+        let params = PublishUpdateParams {
+            topic: Cow::Owned(Topic::new(canonical_url, alternate_urls)),
+            data: None,
+            privacy: PublishUpdatePrivacy::Public,
+            id: Some("urn:uuid:1".to_owned().try_into()?),
+            r#type: None,
+            retry: None,
+        };
+        let encoded_params = serde_html_form::to_string(params)
+            .context("Failed to serialize parameters to application/x-www-form-urlencoded")?;
+        assert_eq!(
+            encoded_params,
+            "topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&id=urn%3Auuid%3A1"
+        );
+        assert!(RevisionId::try_from("#1".to_owned()).is_err());
+        Ok(())
     }
-}
 
-impl PublishUpdateError {
-    /// Returns the corresponding [`PublishUpdateErrorKind`] for this error.
-    #[must_use]
-    pub const fn kind(&self) -> &PublishUpdateErrorKind {
-        &self.kind
+    #[test]
+    fn it_validates_deserialized_revision_id() -> Result<()> {
+        let rev: RevisionId = serde_json::from_str(r#""urn:uuid:1""#)?;
+        assert_eq!(rev.as_str(), "urn:uuid:1");
+        let err = serde_json::from_str::<RevisionId>(r##""#1""##)
+            .err()
+            .context("revision ID starting with # should be rejected")?;
+        assert!(err
+            .to_string()
+            .contains("the revision ID must not start with #"));
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use anyhow::{Context as _, Result};
+    #[test]
+    fn it_compares_revision_id_with_str() -> Result<()> {
+        let rev = RevisionId::try_from("urn:uuid:1".to_owned())?;
+        assert_eq!(rev.as_str(), "urn:uuid:1");
+        assert_eq!(AsRef::<str>::as_ref(&rev), "urn:uuid:1");
+        assert!(rev == "urn:uuid:1");
+        assert!(rev == *"urn:uuid:1");
+        assert!("urn:uuid:1" == rev);
+        assert!(rev != "urn:uuid:2");
+        assert_eq!(rev.to_string(), "urn:uuid:1");
+        Ok(())
+    }
 
-    use super::*;
+    #[test]
+    fn it_serializes_type_if_provided() -> Result<()> {
+        let canonical_url = "https://example.com/books/1".parse()?;
+        let alternate_urls = vec![];
+        let params = PublishUpdateParams {
+            topic: Cow::Owned(Topic::new(canonical_url, alternate_urls)),
+            data: None,
+            privacy: PublishUpdatePrivacy::Public,
+            id: None,
+            r#type: Some("BookUpdated"),
+            retry: None,
+        };
+        let encoded_params = serde_html_form::to_string(params)
+            .context("Failed to serialize parameters to application/x-www-form-urlencoded")?;
+        assert_eq!(
+            encoded_params,
+            "topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&type=BookUpdated"
+        );
+        Ok(())
+    }
 
     #[test]
-    fn it_serializes_privacy_if_private() -> Result<()> {
+    fn it_serializes_retry_as_milliseconds() -> Result<()> {
         let canonical_url = "https://example.com/books/1".parse()?;
-        let alternate_urls = vec!["https://example.com/users/1/books/1".parse()?];
+        let alternate_urls = vec![];
         let params = PublishUpdateParams {
-            topic: Topic::new(canonical_url, alternate_urls),
+            topic: Cow::Owned(Topic::new(canonical_url, alternate_urls)),
             data: None,
-            privacy: PublishUpdatePrivacy::Private,
+            privacy: PublishUpdatePrivacy::Public,
+            id: None,
+            r#type: None,
+            retry: Some(Duration::from_millis(2500)),
         };
         let encoded_params = serde_html_form::to_string(params)
             .context("Failed to serialize parameters to application/x-www-form-urlencoded")?;
         assert_eq!(
             encoded_params,
-            "topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&topic=https%3A%2F%2Fexample.com%2Fusers%\
-             2F1%2Fbooks%2F1&private=on"
+            "topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&retry=2500"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_builds_update_params() -> Result<()> {
+        let client = test_client(UNUSED_HUB_ADDR)?;
+        let update_builder = client
+            .update(Topic::new("https://example.com/books/1".parse()?, vec![]))
+            .data("{}")
+            .private()
+            .id("urn:uuid:1".to_owned().try_into()?)
+            .r#type("BookUpdated")
+            .retry(Duration::from_secs(10));
+        let encoded_params = serde_html_form::to_string(update_builder.params)
+            .context("Failed to serialize parameters to application/x-www-form-urlencoded")?;
+        assert_eq!(
+            encoded_params,
+            "topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&data=%7B%7D&private=on&id=urn%3Auuid%3A1&\
+             type=BookUpdated&retry=10000"
         );
         Ok(())
     }
 
+    #[test]
+    fn it_serializes_typed_data_like_string_data() -> Result<()> {
+        let client = test_client(UNUSED_HUB_ADDR)?;
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        for (typed_data, data) in [
+            (
+                UpdateData::Json(serde_json::json!({
+                    "@id": "https://example.com/books/1",
+                    "isbn": "9780735218789",
+                })),
+                r#"{"@id":"https://example.com/books/1","isbn":"9780735218789"}"#,
+            ),
+            (UpdateData::Text("a&b=c d+é".to_owned()), "a&b=c d+é"),
+            (UpdateData::Raw("<book/>".to_owned()), "<book/>"),
+        ] {
+            assert_eq!(
+                serde_html_form::to_string(
+                    client.update(topic.clone()).typed_data(typed_data).params
+                )?,
+                serde_html_form::to_string(client.update(topic.clone()).data(data).params)?
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn it_skips_serializing_privacy_if_public() -> Result<()> {
         let canonical_url = "https://example.com/books/1".parse()?;
         let alternate_urls = vec![];
         let params = PublishUpdateParams {
-            topic: Topic::new(canonical_url, alternate_urls),
+            topic: Cow::Owned(Topic::new(canonical_url, alternate_urls)),
             data: None,
             privacy: PublishUpdatePrivacy::Public,
+            id: None,
+            r#type: None,
+            retry: None,
         };
         let encoded_params = serde_html_form::to_string(params)
             .context("Failed to serialize parameters to application/x-www-form-urlencoded")?;
@@ -352,4 +4358,521 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn it_serializes_urn_topic() -> Result<()> {
+        let canonical_url = "urn:isbn:9780735218789".parse()?;
+        let alternate_urls = vec!["https://example.com/books/1".parse()?];
+        let params = PublishUpdateParams {
+            topic: Cow::Owned(Topic::new(canonical_url, alternate_urls)),
+            data: None,
+            privacy: PublishUpdatePrivacy::Public,
+            id: None,
+            r#type: None,
+            retry: None,
+        };
+        let encoded_params = serde_html_form::to_string(params)
+            .context("Failed to serialize parameters to application/x-www-form-urlencoded")?;
+        assert_eq!(
+            encoded_params,
+            "topic=urn%3Aisbn%3A9780735218789&topic=https%3A%2F%2Fexample.com%2Fbooks%2F1"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_accepts_hub_url_with_trailing_slash() -> Result<()> {
+        assert!(HubUrl::try_from("https://localhost/.well-known/mercure/".parse::<Url>()?).is_ok());
+        assert!(HubUrl::try_from("https://localhost/hub".parse::<Url>()?).is_err());
+        assert!(
+            HubUrl::try_from("https://localhost/.well-known/mercure//".parse::<Url>()?).is_err()
+        );
+
+        let hub_url = HubUrl::new_unchecked("https://localhost/hub".parse::<Url>()?);
+        assert_eq!(hub_url.to_string(), "https://localhost/hub");
+        Ok(())
+    }
+
+    #[test]
+    fn it_exposes_url_of_hub_url() -> Result<()> {
+        let url = "https://example.com/.well-known/mercure".parse::<Url>()?;
+        let hub_url = HubUrl::try_from(url.clone())?;
+        assert_eq!(hub_url.as_url(), &url);
+        assert_eq!(hub_url.as_ref(), &url);
+        assert_eq!(hub_url.into_url(), url);
+        Ok(())
+    }
+
+    #[test]
+    fn it_serializes_and_deserializes_hub_url() -> Result<()> {
+        let hub_url: HubUrl = "https://localhost/.well-known/mercure".parse()?;
+        let json = serde_json::to_string(&hub_url)?;
+        assert_eq!(json, r#""https://localhost/.well-known/mercure""#);
+        assert_eq!(serde_json::from_str::<HubUrl>(&json)?, hub_url);
+
+        let err = serde_json::from_str::<HubUrl>(r#""https://localhost/hub""#)
+            .err()
+            .context("hub URL with wrong path should not be deserialized")?;
+        assert_eq!(
+            err.to_string(),
+            "the path of the URL must be /.well-known/mercure"
+        );
+        assert!(serde_json::from_str::<HubUrl>(r#""not a URL""#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_hub_url_from_str() -> Result<()> {
+        let hub_url: HubUrl = "https://localhost/.well-known/mercure".parse()?;
+        assert_eq!(hub_url.to_string(), "https://localhost/.well-known/mercure");
+        assert_eq!(
+            HubUrl::try_from("https://localhost/.well-known/mercure")?,
+            hub_url
+        );
+
+        let err = "not a URL"
+            .parse::<HubUrl>()
+            .err()
+            .context("invalid URL should not be parsed")?;
+        assert!(matches!(err.kind(), ParseHubUrlErrorKind::InvalidUrl));
+        let err = HubUrl::try_from("https://localhost/hub")
+            .err()
+            .context("URL with wrong path should not be parsed")?;
+        assert!(matches!(err.kind(), ParseHubUrlErrorKind::InvalidPath));
+        assert_eq!(
+            err.to_string(),
+            "invalid hub URL: the path of the URL must be /.well-known/mercure"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_retries_publish_on_server_error() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+        ])
+        .await?;
+
+        let client = test_client_builder(addr)?
+            .timeout(Duration::from_secs(5))
+            .retry_policy(RetryPolicy::new(2, Duration::from_millis(1)))
+            .build();
+        let revision_id = client
+            .publish_update(
+                Topic::new("https://example.com/books/1".parse::<Url>()?, vec![]),
+                Some("{}"),
+                PublishUpdatePrivacy::Public,
+            )
+            .await?;
+        assert_eq!(revision_id.to_string(), "urn:uuid:1");
+        assert_eq!(server.await??.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn it_computes_retry_delay_with_exponential_backoff() {
+        let retry_policy = RetryPolicy::new(4, Duration::from_millis(100));
+        assert_eq!(retry_policy.delay(1), Some(Duration::from_millis(100)));
+        assert_eq!(retry_policy.delay(2), Some(Duration::from_millis(200)));
+        assert_eq!(retry_policy.delay(3), Some(Duration::from_millis(400)));
+        assert_eq!(retry_policy.delay(4), None);
+        assert_eq!(RetryPolicy::new(0, Duration::ZERO).delay(1), None);
+    }
+
+    #[test]
+    fn it_round_trips_builder_options_through_client_config() -> Result<()> {
+        let client = test_client_builder(UNUSED_HUB_ADDR)?
+            .timeout(Duration::from_secs(10))
+            .retry_policy(RetryPolicy::new(3, Duration::from_millis(500)))
+            .build();
+        let client_config = client.config();
+        assert_eq!(client_config.timeout(), Some(Duration::from_secs(10)));
+        assert_eq!(
+            client_config.retry_policy(),
+            Some(RetryPolicy::new(3, Duration::from_millis(500)))
+        );
+        let client = Client::from_config(
+            reqwest::Client::new(),
+            test_publisher_jwt()?,
+            serde_json::from_str(&serde_json::to_string(&client_config)?)?,
+        );
+        assert_eq!(client.config(), client_config);
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn it_publishes_with_traceparent() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nurn:uuid:2",
+        ])
+        .await?;
+
+        let topic = Topic::new("https://example.com/books/1".parse::<Url>()?, vec![]);
+        let client = test_client_builder(addr)?
+            .traceparent(|| {
+                Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_owned())
+            })
+            .build();
+        client
+            .publish_update(&topic, Some("{}"), PublishUpdatePrivacy::Public)
+            .await?;
+        let client = test_client_builder(addr)?
+            .traceparent(|| {
+                Some("00-00000000000000000000000000000000-00f067aa0ba902b7-01".to_owned())
+            })
+            .build();
+        client
+            .publish_update(&topic, Some("{}"), PublishUpdatePrivacy::Public)
+            .await?;
+
+        let reqs = server.await??;
+        assert!(reqs[0]
+            .contains("traceparent: 00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01\r\n"));
+        assert!(!reqs[1].contains("traceparent: "));
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn it_validates_traceparent() {
+        assert!(is_valid_traceparent(
+            b"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        ));
+        assert!(is_valid_traceparent(
+            b"01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-future"
+        ));
+        assert!(!is_valid_traceparent(
+            b"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-future"
+        ));
+        assert!(!is_valid_traceparent(
+            b"ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        ));
+        assert!(!is_valid_traceparent(
+            b"00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01"
+        ));
+        assert!(!is_valid_traceparent(
+            b"00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01"
+        ));
+        assert!(!is_valid_traceparent(
+            b"00-4bf92f3577b34da6a3ce929d0e0e4736"
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_publishes_with_authorization_cookie() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+        ])
+        .await?;
+
+        let publisher_jwt = test_publisher_jwt()?;
+        let client = test_client_builder(addr)?
+            .authorization_mode(AuthorizationMode::Cookie)
+            .build();
+        assert_eq!(
+            client.config().authorization_mode(),
+            AuthorizationMode::Cookie
+        );
+        client
+            .publish_update(
+                Topic::new("https://example.com/books/1".parse::<Url>()?, vec![]),
+                Some("{}"),
+                PublishUpdatePrivacy::Public,
+            )
+            .await?;
+
+        let reqs = server.await??;
+        assert!(reqs[0].contains(&format!("cookie: mercureAuthorization={publisher_jwt}\r\n")));
+        assert!(!reqs[0].contains("authorization: "));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_to_publish_on_error_status() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 403 Forbidden\r\nConnection: close\r\nContent-Length: 9\r\n\r\nForbidden",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let err = client
+            .publish_update(
+                Topic::new("https://example.com/books/1".parse::<Url>()?, vec![]),
+                Some("{}"),
+                PublishUpdatePrivacy::Public,
+            )
+            .await
+            .err()
+            .context("publishing should fail with an error status")?;
+        assert!(matches!(
+            err.kind(),
+            PublishUpdateErrorKind::UnexpectedStatus { status, body }
+                if *status == reqwest::StatusCode::FORBIDDEN && body == "Forbidden"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Mercure hub responded with an unexpected status: 403 Forbidden"
+        );
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_cancels_publishing() -> Result<()> {
+        // Never accepts the connection, so the request stays in flight.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        let client = test_client(addr)?;
+        let err = client
+            .update(Topic::new(
+                "https://example.com/books/1".parse::<Url>()?,
+                vec![],
+            ))
+            .data("{}")
+            .cancel_on(tokio::time::sleep(Duration::from_millis(10)))
+            .send()
+            .await
+            .err()
+            .context("publishing should be cancelled")?;
+        assert!(matches!(err.kind(), PublishUpdateErrorKind::Cancelled));
+        assert_eq!(err.to_string(), "publishing was cancelled");
+        drop(listener);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_publishes_update_owned_in_spawned_task() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let handle = {
+            let data = String::from("{}");
+            tokio::spawn(client.publish_update_owned(
+                Topic::new("https://example.com/books/1".parse::<Url>()?, vec![]),
+                Some(data),
+                PublishUpdatePrivacy::Public,
+            ))
+        };
+        drop(client);
+        let revision_id = handle.await??;
+        assert_eq!(revision_id.to_string(), "urn:uuid:1");
+
+        let reqs = server.await??;
+        assert!(reqs[0].ends_with("topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&data=%7B%7D"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_reads_revision_id_from_location_header() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nLocation: urn:uuid:2\r\nContent-Length: \
+              10\r\n\r\nurn:uuid:1",
+            b"HTTP/1.1 201 Created\r\nConnection: close\r\nLocation: urn:uuid:3\r\n\
+              Content-Length: 0\r\n\r\n",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let topic = Topic::new("https://example.com/books/1".parse::<Url>()?, vec![]);
+        let rev = client
+            .publish_update(&topic, Some("{}"), PublishUpdatePrivacy::Public)
+            .await?;
+        assert_eq!(rev, "urn:uuid:2");
+        let rev = client
+            .publish_update(&topic, Some("{}"), PublishUpdatePrivacy::Public)
+            .await?;
+        assert_eq!(rev, "urn:uuid:3");
+
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_publishes_updates_to_borrowed_topic() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:2",
+        ])
+        .await?;
+
+        let client = test_client(addr)?;
+        let topic = Topic::new("https://example.com/books/1".parse::<Url>()?, vec![]);
+        let rev = client
+            .publish_update(&topic, Some("1"), PublishUpdatePrivacy::Public)
+            .await?;
+        assert_eq!(rev, "urn:uuid:1");
+        let rev = client.update(&topic).data("2").private().send().await?;
+        assert_eq!(rev, "urn:uuid:2");
+
+        let reqs = server.await??;
+        assert!(reqs[0].ends_with("topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&data=1"));
+        assert!(reqs[1].ends_with("topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&data=2&private=on"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_publishes_updates_through_publisher() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:2",
+        ])
+        .await?;
+
+        let client = test_client_builder(addr)?
+            .max_concurrent_publishes(NonZeroUsize::MIN)
+            .build();
+        let (publisher, task) = client.spawn_publisher(NonZeroUsize::MIN);
+        let producer = tokio::spawn({
+            let publisher = publisher.clone();
+            async move {
+                publisher
+                    .send(
+                        Topic::new("https://example.com/books/1".parse()?, vec![]),
+                        Some("1".into()),
+                        PublishUpdatePrivacy::Public,
+                    )
+                    .await
+                    .map_err(anyhow::Error::from)
+            }
+        });
+        let rev = publisher
+            .send(
+                Topic::new("https://example.com/books/2".parse()?, vec![]),
+                Some("2".into()),
+                PublishUpdatePrivacy::Private,
+            )
+            .await?;
+        let other_rev = producer.await??;
+        let mut revs = [rev.as_str(), other_rev.as_str()];
+        revs.sort_unstable();
+        assert_eq!(revs, ["urn:uuid:1", "urn:uuid:2"]);
+
+        task.shutdown().await;
+        let err = publisher
+            .send(
+                Topic::new("https://example.com/books/3".parse()?, vec![]),
+                None,
+                PublishUpdatePrivacy::Public,
+            )
+            .await
+            .err()
+            .context("publishing after shutdown should fail")?;
+        assert!(matches!(
+            err.kind(),
+            PublishUpdateErrorKind::PublisherShutDown
+        ));
+
+        let reqs = server.await??;
+        assert_eq!(reqs.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_drains_queued_updates_on_publisher_shutdown() -> Result<()> {
+        use tokio::io::AsyncWriteExt as _;
+
+        // Responds slowly, so that updates are still queued when shutting down.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let (first_request, first_request_receiver) = oneshot::channel();
+        let server = tokio::spawn(async move {
+            let mut first_request = Some(first_request);
+            let mut reqs = Vec::new();
+            for i in 1..=3 {
+                let (mut stream, _) = listener.accept().await?;
+                reqs.push(read_request(&mut stream).await?);
+                if let Some(first_request) = first_request.take() {
+                    let _ = first_request.send(());
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:{i}"
+                );
+                stream.write_all(response.as_bytes()).await?;
+            }
+            Ok::<_, anyhow::Error>(reqs)
+        });
+
+        let client = test_client_builder(addr)?
+            .max_concurrent_publishes(NonZeroUsize::MIN)
+            .build();
+        let (publisher, task) = client.spawn_publisher(NonZeroUsize::new(3).unwrap());
+        let sends = future::join_all((1..=3).map(|book_id| {
+            let publisher = &publisher;
+            async move {
+                let topic = Topic::new(
+                    format!("https://example.com/books/{book_id}").parse()?,
+                    vec![],
+                );
+                publisher
+                    .send(topic, None, PublishUpdatePrivacy::Public)
+                    .await
+                    .map_err(anyhow::Error::from)
+            }
+        }));
+        let shutdown = async {
+            // By the time the first update reaches the hub, the others are
+            // queued behind it.
+            first_request_receiver.await?;
+            task.shutdown().await;
+            Ok::<_, anyhow::Error>(())
+        };
+        let (results, shutdown) = tokio::join!(sends, shutdown);
+        shutdown?;
+        let revs = results.into_iter().collect::<Result<Vec<_>>>()?;
+        assert_eq!(revs, ["urn:uuid:1", "urn:uuid:2", "urn:uuid:3"]);
+
+        let reqs = server.await??;
+        assert_eq!(reqs.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_publishes_updates_in_order() -> Result<()> {
+        let (addr, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 10\r\n\r\nurn:uuid:1",
+            b"HTTP/1.1 403 Forbidden\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        ])
+        .await?;
+
+        let client = test_client_builder(addr)?
+            .max_concurrent_publishes(NonZeroUsize::MIN)
+            .build();
+        let results = client
+            .publish_updates([
+                (
+                    Topic::new("https://example.com/books/1".parse()?, vec![]),
+                    Some("1"),
+                    PublishUpdatePrivacy::Public,
+                ),
+                (
+                    Topic::new("https://example.com/books/2".parse()?, vec![]),
+                    Some("2"),
+                    PublishUpdatePrivacy::Private,
+                ),
+            ])
+            .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].as_ref().ok(),
+            Some(&RevisionId("urn:uuid:1".into()))
+        );
+        assert!(matches!(
+            results[1]
+                .as_ref()
+                .err()
+                .context("second update should fail")?
+                .kind(),
+            PublishUpdateErrorKind::UnexpectedStatus { .. }
+        ));
+        server.await??;
+        Ok(())
+    }
 }
@@ -1,3 +1,9 @@
+use std::fmt;
+use std::fmt::Write as _;
+
+use crate::jwt::SubscriberJwtMaxAge;
+use crate::{SubscriberJwt, HUB_PATH};
+
 /// [The Mercure Protocol, Section 6](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-6)
 ///
 /// > If the publisher or the subscriber is a web browser, it SHOULD send a
@@ -7,3 +13,273 @@ pub const MERCURE_AUTHORIZATION_COOKIE_NAME: &str = "mercureAuthorization";
 
 /// [RFC 6265bis, Section 5.5](https://datatracker.ietf.org/doc/html/draft-ietf-httpbis-rfc6265bis#section-5.5)
 pub const MAX_AGE_LIMIT: std::time::Duration = std::time::Duration::from_secs(34_560_000);
+
+/// A builder for the `Set-Cookie` header value of the "mercureAuthorization"
+/// cookie, with full control over its attributes.
+///
+/// See [`authorization_cookie`] for the common case.
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// #
+/// use mercure::cookie::{CookieBuilder, SameSite};
+/// use mercure::jwt::SubscriberJwtSecret;
+/// use mercure::{SubscriberJwt, TopicSelector};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let subscriber_jwt_secret =
+///     SubscriberJwtSecret::from(b"!ChangeThisMercureHubJWTSecretKey!".to_vec());
+/// let subscriber_jwt = SubscriberJwt::new(&subscriber_jwt_secret, None, vec![
+///     TopicSelector::UriTemplate("https://example.com/users/1/books/{book_id}".try_into()?),
+/// ])?;
+///
+/// let set_cookie = CookieBuilder::new(&subscriber_jwt)
+///     .domain("example.com")
+///     .same_site(SameSite::Lax)
+///     .secure(true)
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "builders do nothing unless `build` is called"]
+pub struct CookieBuilder<'a> {
+    subscriber_jwt: &'a SubscriberJwt,
+    domain: Option<&'a str>,
+    path: &'a str,
+    same_site: SameSite,
+    max_age: Option<SubscriberJwtMaxAge>,
+    secure: bool,
+    http_only: bool,
+}
+
+/// The `SameSite` attribute of a cookie.
+///
+/// [RFC 6265bis, Section 4.1.2.7](https://datatracker.ietf.org/doc/html/draft-ietf-httpbis-rfc6265bis#section-4.1.2.7)
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum SameSite {
+    /// The cookie is only sent with same-site requests.
+    #[default]
+    Strict,
+    /// The cookie is also sent with top-level cross-site navigations.
+    Lax,
+    /// The cookie is sent with all requests. Requires the `Secure` attribute.
+    None,
+}
+
+/// Returns the `Set-Cookie` header value to hand the given subscriber JWT to
+/// a web browser.
+///
+/// The cookie is scoped to the Mercure hub path, and is `HttpOnly` and
+/// `SameSite=Strict`. The `Max-Age` attribute is set if a max-age is given,
+/// and the `Secure` attribute is set if `secure` is `true`.
+///
+/// [The Mercure Protocol, Section 6](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-6)
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// #
+/// use mercure::cookie::authorization_cookie;
+/// use mercure::jwt::{SubscriberJwtMaxAge, SubscriberJwtSecret};
+/// use mercure::{SubscriberJwt, TopicSelector};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let subscriber_jwt_secret =
+///     SubscriberJwtSecret::from(b"!ChangeThisMercureHubJWTSecretKey!".to_vec());
+/// let subscriber_jwt_max_age = SubscriberJwtMaxAge::from_mins(15)?;
+/// let subscriber_jwt = SubscriberJwt::new(
+///     &subscriber_jwt_secret,
+///     Some(subscriber_jwt_max_age),
+///     vec![TopicSelector::UriTemplate(
+///         "https://example.com/users/1/books/{book_id}".try_into()?,
+///     )],
+/// )?;
+///
+/// let set_cookie = authorization_cookie(&subscriber_jwt, Some(subscriber_jwt_max_age), true);
+/// assert!(set_cookie.ends_with(
+///     "; Path=/.well-known/mercure; Max-Age=900; HttpOnly; SameSite=Strict; Secure"
+/// ));
+/// # Ok(())
+/// # }
+/// ```
+pub fn authorization_cookie(
+    subscriber_jwt: &SubscriberJwt,
+    max_age: Option<SubscriberJwtMaxAge>,
+    secure: bool,
+) -> String {
+    let mut cookie_builder = CookieBuilder::new(subscriber_jwt).secure(secure);
+    if let Some(max_age) = max_age {
+        cookie_builder = cookie_builder.max_age(max_age);
+    }
+
+    cookie_builder.build()
+}
+
+impl<'a> CookieBuilder<'a> {
+    /// Constructs a new `CookieBuilder` for the given subscriber JWT.
+    ///
+    /// The defaults are the same as for [`authorization_cookie`]: the cookie
+    /// is scoped to the Mercure hub path, and is `HttpOnly`,
+    /// `SameSite=Strict`, and not `Secure`.
+    pub fn new(subscriber_jwt: &'a SubscriberJwt) -> Self {
+        Self {
+            subscriber_jwt,
+            domain: None,
+            path: HUB_PATH,
+            same_site: SameSite::Strict,
+            max_age: None,
+            secure: false,
+            http_only: true,
+        }
+    }
+
+    /// Sets the `Domain` attribute, e.g. to share the cookie with a Mercure
+    /// hub on a subdomain.
+    pub fn domain(mut self, domain: &'a str) -> Self {
+        self.domain = Some(domain);
+        self
+    }
+
+    /// Sets the `Path` attribute. Defaults to `/.well-known/mercure`.
+    pub fn path(mut self, path: &'a str) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Sets the `SameSite` attribute. Defaults to [`SameSite::Strict`].
+    ///
+    /// [`SameSite::None`] requires the `Secure` attribute, so it is always set
+    /// in that case.
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Sets the `Max-Age` attribute.
+    pub fn max_age(mut self, max_age: SubscriberJwtMaxAge) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets whether to set the `Secure` attribute. Defaults to `false`.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets whether to set the `HttpOnly` attribute. Defaults to `true`.
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Returns the `Set-Cookie` header value.
+    pub fn build(&self) -> String {
+        let mut cookie = format!(
+            "{MERCURE_AUTHORIZATION_COOKIE_NAME}={subscriber_jwt}",
+            subscriber_jwt = self.subscriber_jwt
+        );
+        if let Some(domain) = self.domain {
+            write!(cookie, "; Domain={domain}").unwrap();
+        }
+        write!(cookie, "; Path={path}", path = self.path).unwrap();
+        if let Some(max_age) = self.max_age {
+            let max_age = std::time::Duration::from(max_age).min(MAX_AGE_LIMIT);
+            write!(cookie, "; Max-Age={secs}", secs = max_age.as_secs()).unwrap();
+        }
+        if self.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        write!(cookie, "; SameSite={same_site}", same_site = self.same_site).unwrap();
+        if self.secure || self.same_site == SameSite::None {
+            cookie.push_str("; Secure");
+        }
+
+        cookie
+    }
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Strict => write!(f, "Strict"),
+            Self::Lax => write!(f, "Lax"),
+            Self::None => write!(f, "None"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::jwt::SubscriberJwtSecret;
+    use crate::TopicSelector;
+
+    #[test]
+    fn it_creates_authorization_cookie() -> Result<()> {
+        let subscriber_jwt_secret =
+            SubscriberJwtSecret::from(b"!ChangeThisMercureHubJWTSecretKey!".to_vec());
+        let subscriber_jwt =
+            SubscriberJwt::new(&subscriber_jwt_secret, None, vec![TopicSelector::Wildcard])?;
+
+        assert_eq!(
+            authorization_cookie(&subscriber_jwt, None, false),
+            format!(
+                "mercureAuthorization={subscriber_jwt}; Path=/.well-known/mercure; HttpOnly; \
+                 SameSite=Strict"
+            )
+        );
+        assert_eq!(
+            authorization_cookie(&subscriber_jwt, Some(SubscriberJwtMaxAge::MAX), true),
+            format!(
+                "mercureAuthorization={subscriber_jwt}; Path=/.well-known/mercure; \
+                 Max-Age=34560000; HttpOnly; SameSite=Strict; Secure"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_builds_cookie_with_attributes() -> Result<()> {
+        let subscriber_jwt_secret =
+            SubscriberJwtSecret::from(b"!ChangeThisMercureHubJWTSecretKey!".to_vec());
+        let subscriber_jwt =
+            SubscriberJwt::new(&subscriber_jwt_secret, None, vec![TopicSelector::Wildcard])?;
+
+        assert_eq!(
+            CookieBuilder::new(&subscriber_jwt)
+                .domain("example.com")
+                .path("/")
+                .same_site(SameSite::Lax)
+                .max_age(SubscriberJwtMaxAge::from_mins(15)?)
+                .http_only(false)
+                .build(),
+            format!(
+                "mercureAuthorization={subscriber_jwt}; Domain=example.com; Path=/; Max-Age=900; \
+                 SameSite=Lax"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_sets_secure_with_same_site_none() -> Result<()> {
+        let subscriber_jwt_secret =
+            SubscriberJwtSecret::from(b"!ChangeThisMercureHubJWTSecretKey!".to_vec());
+        let subscriber_jwt =
+            SubscriberJwt::new(&subscriber_jwt_secret, None, vec![TopicSelector::Wildcard])?;
+
+        assert!(CookieBuilder::new(&subscriber_jwt)
+            .same_site(SameSite::None)
+            .secure(false)
+            .build()
+            .ends_with("; SameSite=None; Secure"));
+        Ok(())
+    }
+}
@@ -4,15 +4,15 @@
 //!
 //! # Example
 //!
-//! ```
+#![cfg_attr(feature = "client", doc = "```")]
+#![cfg_attr(not(feature = "client"), doc = "```ignore")]
 //! use std::error::Error;
 //!
 //! use mercure::{HubUrl, PublisherJwt, TopicSelector};
-//! use url::Url;
 //!
 //! fn main() -> Result<(), Box<dyn Error>> {
 //!     let http_client = reqwest::Client::new();
-//!     let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+//!     let hub_url: HubUrl = "https://localhost/.well-known/mercure".parse()?;
 //!     let publisher_jwt = PublisherJwt::new(
 //!         &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
 //!         vec![TopicSelector::Wildcard],
@@ -25,17 +25,17 @@
 //!
 //! # Publishing update to Mercure hub
 //!
-//! ```no_run
+#![cfg_attr(feature = "client", doc = "```no_run")]
+#![cfg_attr(not(feature = "client"), doc = "```ignore")]
 //! use std::error::Error;
 //!
 //! use mercure::client::PublishUpdatePrivacy;
 //! use mercure::{HubUrl, PublisherJwt, Topic, TopicSelector};
-//! use url::Url;
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn Error>> {
 //!     let http_client = reqwest::Client::new();
-//!     let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+//!     let hub_url: HubUrl = "https://localhost/.well-known/mercure".parse()?;
 //!     let publisher_jwt = PublisherJwt::new(
 //!         &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
 //!         vec![TopicSelector::Wildcard],
@@ -52,6 +52,39 @@
 //! }
 //! ```
 //!
+//! # Subscribing to updates from Mercure hub
+//!
+#![cfg_attr(feature = "client", doc = "```no_run")]
+#![cfg_attr(not(feature = "client"), doc = "```ignore")]
+//! use std::error::Error;
+//!
+//! use futures_util::StreamExt as _;
+//! use mercure::{HubUrl, PublisherJwt, TopicSelector};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn Error>> {
+//!     let http_client = reqwest::Client::new();
+//!     let hub_url: HubUrl = "https://localhost/.well-known/mercure".parse()?;
+//!     let publisher_jwt = PublisherJwt::new(
+//!         &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+//!         vec![TopicSelector::Wildcard],
+//!     )?;
+//!
+//!     let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+//!
+//!     let topic_selectors = vec![TopicSelector::UriTemplate(
+//!         "https://example.com/books/{book_id}".try_into()?,
+//!     )];
+//!
+//!     let mut update_stream = client.subscribe(topic_selectors, None).await?;
+//!     while let Some(update) = update_stream.next().await {
+//!         let update = update?;
+//!         println!("{data}", data = update.data());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
 //! # Issuing subscriber JWT access token
 //!
 //! ```
@@ -69,18 +102,128 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! # Feature flags
+//!
+//! - `client` (enabled by default): the `Client` for publishing and
+//!   subscribing to updates, backed by `reqwest`. Disable it with
+//!   `default-features = false` if you only need to issue JWT access tokens
+//!   and match topic selectors.
+//! - `compression`: enables `reqwest`'s gzip and deflate support, so that
+//!   `ReqwestTransport` asks the Mercure hub to compress responses, e.g. the
+//!   event stream when subscribing to updates, and decompresses them. Can be
+//!   disabled per subscription with `SubscriptionBuilder::compression`.
+//!   Implies `client`.
+//! - `serde-errors`: implements `Serialize` for all error types.
+//! - `testing`: a mock hub for testing publishers, see below. Implies
+//!   `client`.
+//! - `tracing`: emits [`tracing`] spans and events when publishing updates
+//!   (attempts, response status and latency) and subscribing to updates
+//!   (connections, reconnections and received updates). Spans carry the
+//!   canonical URL of the topic and the privacy setting, or the subscription
+//!   URL. The publisher JWT, secret keys and the data of updates are never
+//!   recorded. Also allows sending a W3C `traceparent` header with publish
+//!   requests, with `ClientBuilder::traceparent`. Implies `client`.
+//! - `wasm`: support for web browsers on `wasm32-unknown-unknown`, see below.
+//!   Implies `client`.
+//!
+//! [`tracing`]: https://docs.rs/tracing
+//!
+//! # Testing publishers
+//!
+//! With the `testing` feature enabled, `mercure::testing::MockHub` is a
+//! transport which records the updates published by a client built with
+//! `ClientBuilder::transport`, instead of sending them to the Mercure hub, so
+//! that tests can assert on them.
+//!
+//! # WebAssembly
+//!
+//! With the `wasm` feature enabled, the client can be used in web browsers on
+//! `wasm32-unknown-unknown`, e.g. from a Leptos or Yew frontend. `ReqwestTransport`
+//! is backed by the browser's `fetch` API there, both for publishing and for
+//! subscribing to updates. The event stream is read from the response body rather
+//! than with `EventSource`, which cannot send the `Authorization` and
+//! `Last-Event-ID` headers. Delays between retries and reconnections, and connect
+//! timeouts, use `setTimeout` instead of Tokio timers.
+//!
+//! The following are not available in web browsers:
+//!
+//! - `Client::spawn_publisher`, which needs a Tokio runtime.
+//! - Issuing and decoding JWTs with an expiry, as reading the system time panics
+//!   on `wasm32-unknown-unknown`. JWTs should be issued by your server anyway, as
+//!   the secret key must not be shipped to browsers.
+//! - The `tracing` feature, which measures latency with `std::time::Instant`.
+//!
+//! `AuthorizationMode::Cookie` relies on the browser to send the
+//! "mercureAuthorization" cookie, as the `Cookie` header cannot be set by `fetch`.
+//!
+//! # Error handling
+//!
+//! All error types implement [`std::error::Error`] and are `Send + Sync +
+//! 'static`, so they can be wrapped in your own error types, e.g. with
+//! [`thiserror`]'s `#[from]` attribute.
+//!
+//! With the `serde-errors` feature enabled, all error types also implement
+//! [`Serialize`] as their kind (if any) and message, which is useful for
+//! structured logging.
+//!
+//! [`Serialize`]: https://docs.rs/serde/latest/serde/trait.Serialize.html
+//! [`thiserror`]: https://docs.rs/thiserror
+//!
+//! ```
+//! use mercure::jwt::{SubscriberJwtError, SubscriberJwtSecret};
+//! use mercure::topic_selector::ParseUriTemplateError;
+//! use mercure::{SubscriberJwt, TopicSelector};
+//!
+//! #[derive(Debug, thiserror::Error)]
+//! enum AppError {
+//!     #[error(transparent)]
+//!     ParseUriTemplate(#[from] ParseUriTemplateError),
+//!     #[error(transparent)]
+//!     SubscriberJwt(#[from] SubscriberJwtError),
+//! }
+//!
+//! fn issue_subscriber_jwt(
+//!     subscriber_jwt_secret: &SubscriberJwtSecret,
+//!     user_id: u64,
+//! ) -> Result<SubscriberJwt, AppError> {
+//!     let uri_template = format!("https://example.com/users/{user_id}/books/{{book_id}}");
+//!     let subscriber_jwt = SubscriberJwt::new(subscriber_jwt_secret, None, vec![
+//!         TopicSelector::UriTemplate(uri_template.as_str().try_into()?),
+//!     ])?;
+//!     Ok(subscriber_jwt)
+//! }
+//! ```
 
+#[cfg(feature = "client")]
 pub use self::client::{Client, HubUrl};
-pub use self::jwt::{PublisherJwt, SubscriberJwt};
+pub use self::jwt::{Jwt, PublisherJwt, SubscriberJwt};
 pub use self::topic::Topic;
 pub use self::topic_selector::TopicSelector;
 
+#[cfg(feature = "client")]
 pub mod client;
 pub mod cookie;
 pub mod jwt;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "client")]
+mod time;
 pub mod topic;
 pub mod topic_selector;
+#[cfg(feature = "client")]
+pub mod transport;
+#[cfg(feature = "client")]
+pub mod update;
+
+/// [The Mercure Protocol, Section 2](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-2)
+///
+/// > The URL of the hub MUST be the "well-known" [[RFC5785]] fixed path
+/// > "/.well-known/mercure".
+///
+/// [RFC5785]: https://datatracker.ietf.org/doc/html/rfc5785
+const HUB_PATH: &str = "/.well-known/mercure";
 
 #[doc = include_str!("../../../README.md")]
-#[cfg(doctest)]
+#[cfg(all(doctest, feature = "client"))]
 pub struct ReadmeDoctests;
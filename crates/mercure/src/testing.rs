@@ -0,0 +1,320 @@
+//! Utilities for testing code which publishes updates, without a Mercure hub.
+//!
+//! Requires the `testing` feature.
+
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::stream;
+use reqwest::StatusCode;
+use url::{form_urlencoded, Url};
+
+use crate::client::{PublishUpdatePrivacy, RevisionId};
+use crate::transport::{Transport, TransportRequest, TransportResponse};
+use crate::Topic;
+
+/// An in-memory stand-in for the Mercure hub, which records published updates.
+///
+/// It is a [`Transport`], so hand it to a [`GenericClient`] with
+/// [`ClientBuilder::transport`]. Clones share the same recorded updates.
+///
+/// The checks made by the [`GenericClient`] before sending a publish request
+/// still apply, e.g. [`PublishUpdateErrorKind::InsecureHubUrl`] and
+/// [`PublishUpdateErrorKind::Unauthorized`].
+///
+/// Subscribing succeeds, but no updates are received.
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// #
+/// use mercure::client::PublishUpdatePrivacy;
+/// use mercure::testing::MockHub;
+/// use mercure::{HubUrl, PublisherJwt, Topic, TopicSelector};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+/// let publisher_jwt = PublisherJwt::new(
+///     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+///     vec![TopicSelector::Wildcard],
+/// )?;
+/// let mock_hub = MockHub::new();
+/// let client = mercure::Client::builder(hub_url, publisher_jwt)
+///     .transport(mock_hub.clone())
+///     .build();
+///
+/// let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+/// client
+///     .publish_update(topic.clone(), Some("{}"), PublishUpdatePrivacy::Private)
+///     .await?;
+///
+/// let published = mock_hub.published();
+/// assert_eq!(published.len(), 1);
+/// assert_eq!(published[0].topic(), &topic);
+/// assert_eq!(published[0].data(), Some("{}"));
+/// assert_eq!(published[0].privacy(), PublishUpdatePrivacy::Private);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`GenericClient`]: crate::client::GenericClient
+/// [`ClientBuilder::transport`]: crate::client::ClientBuilder::transport
+/// [`PublishUpdateErrorKind::InsecureHubUrl`]: crate::client::PublishUpdateErrorKind::InsecureHubUrl
+/// [`PublishUpdateErrorKind::Unauthorized`]: crate::client::PublishUpdateErrorKind::Unauthorized
+#[derive(Clone, Debug, Default)]
+pub struct MockHub {
+    published: Arc<Mutex<Vec<RecordedUpdate>>>,
+}
+
+/// An update recorded by a [`MockHub`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RecordedUpdate {
+    topic: Topic,
+    data: Option<String>,
+    privacy: PublishUpdatePrivacy,
+    id: Option<RevisionId>,
+    r#type: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl MockHub {
+    /// Constructs a new `MockHub` with no recorded updates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the updates published so far, in order.
+    ///
+    /// This returns an owned snapshot rather than `&[RecordedUpdate]`, as the
+    /// recorded updates are shared with the clones of this `MockHub`, behind
+    /// a lock, and may be appended to concurrently.
+    pub fn published(&self) -> Vec<RecordedUpdate> {
+        self.published
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Discards the recorded updates.
+    pub fn clear(&self) {
+        self.published
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Records the update in the given publish request body, and returns its
+    /// revision ID, as the Mercure hub would.
+    fn record(&self, body: &[u8]) -> RevisionId {
+        let mut urls = Vec::new();
+        let mut data = None;
+        let mut privacy = PublishUpdatePrivacy::Public;
+        let mut id = None;
+        let mut r#type = None;
+        let mut retry = None;
+        for (name, value) in form_urlencoded::parse(body) {
+            match &*name {
+                "topic" => urls.push(
+                    value
+                        .parse::<Url>()
+                        .expect("topic should be serialized from a valid `Url`"),
+                ),
+                "data" => data = Some(value.into_owned()),
+                "private" => privacy = PublishUpdatePrivacy::from(value == "on"),
+                "id" => id = Some(value.into_owned()),
+                "type" => r#type = Some(value.into_owned()),
+                "retry" => {
+                    retry = value.parse().ok().map(Duration::from_millis);
+                },
+                _ => {},
+            }
+        }
+
+        let mut published = self
+            .published
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let id: RevisionId = id
+            .unwrap_or_else(|| format!("urn:uuid:00000000-0000-0000-0000-{:012}", published.len()))
+            .try_into()
+            .expect("revision ID should not start with `#`");
+        published.push(RecordedUpdate {
+            topic: urls.into_iter().collect(),
+            data,
+            privacy,
+            id: Some(id.clone()),
+            r#type,
+            retry,
+        });
+
+        id
+    }
+}
+
+impl Transport for MockHub {
+    type Body = Pin<Box<dyn Stream<Item = Result<Bytes, Infallible>> + Send>>;
+    type Error = Infallible;
+
+    async fn post(
+        &self,
+        request: TransportRequest,
+    ) -> Result<TransportResponse<Self::Body>, Self::Error> {
+        let revision_id = self.record(request.body());
+        Ok(TransportResponse::new(
+            StatusCode::OK,
+            Box::pin(stream::iter([Ok(Bytes::from(revision_id.to_string()))])),
+        ))
+    }
+
+    async fn get(
+        &self,
+        _request: TransportRequest,
+    ) -> Result<TransportResponse<Self::Body>, Self::Error> {
+        Ok(TransportResponse::new(
+            StatusCode::OK,
+            Box::pin(stream::pending()),
+        ))
+    }
+}
+
+impl RecordedUpdate {
+    /// Returns the topic of the update.
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// Returns the data of the update, if any.
+    pub fn data(&self) -> Option<&str> {
+        self.data.as_deref()
+    }
+
+    /// Returns the privacy setting of the update.
+    pub fn privacy(&self) -> PublishUpdatePrivacy {
+        self.privacy
+    }
+
+    /// Returns the revision ID of the update, either as provided by the
+    /// publisher, or as generated by the [`MockHub`].
+    pub fn id(&self) -> Option<&RevisionId> {
+        self.id.as_ref()
+    }
+
+    /// Returns the type of the update, if any.
+    pub fn r#type(&self) -> Option<&str> {
+        self.r#type.as_deref()
+    }
+
+    /// Returns the reconnection time of the update, if any.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::client::tests::{test_client_builder, UNUSED_HUB_ADDR};
+    use crate::TopicSelector;
+
+    #[tokio::test]
+    async fn it_records_published_updates() -> Result<()> {
+        let mock_hub = MockHub::new();
+        let client = test_client_builder(UNUSED_HUB_ADDR)?
+            .transport(mock_hub.clone())
+            .build();
+
+        let topic = Topic::builder("https://example.com/books/1".parse()?)
+            .alternate("urn:isbn:9780735218789".parse()?)
+            .build();
+        let revision_id = client
+            .update(topic.clone())
+            .data("a&b=c d+é")
+            .private()
+            .id("urn:uuid:1".to_owned().try_into()?)
+            .r#type("BookUpdated")
+            .retry(Duration::from_secs(10))
+            .send()
+            .await?;
+        assert_eq!(revision_id.to_string(), "urn:uuid:1");
+        let generated_revision_id = client
+            .publish_ping(topic.clone(), PublishUpdatePrivacy::Public)
+            .await?;
+
+        let published = mock_hub.published();
+        assert_eq!(published.len(), 2);
+        assert_eq!(published[0].topic(), &topic);
+        assert_eq!(published[0].data(), Some("a&b=c d+é"));
+        assert_eq!(published[0].privacy(), PublishUpdatePrivacy::Private);
+        assert_eq!(published[0].id(), Some(&revision_id));
+        assert_eq!(published[0].r#type(), Some("BookUpdated"));
+        assert_eq!(published[0].retry(), Some(Duration::from_secs(10)));
+        assert_eq!(published[1].data(), None);
+        assert_eq!(published[1].privacy(), PublishUpdatePrivacy::Public);
+        assert_eq!(published[1].id(), Some(&generated_revision_id));
+
+        mock_hub.clear();
+        assert!(mock_hub.published().is_empty());
+
+        client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_records_updates_published_with_shorthands() -> Result<()> {
+        let mock_hub = MockHub::new();
+        let client = test_client_builder(UNUSED_HUB_ADDR)?
+            .transport(mock_hub.clone())
+            .build();
+
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        client
+            .publish_update_with_id(
+                topic.clone(),
+                Some("1"),
+                PublishUpdatePrivacy::Private,
+                "urn:uuid:1".to_owned().try_into()?,
+            )
+            .await?;
+        client
+            .publish_update_with_type(
+                topic.clone(),
+                Some("2"),
+                PublishUpdatePrivacy::Public,
+                "BookUpdated",
+            )
+            .await?;
+        client
+            .publish_update_with_retry(
+                topic,
+                None,
+                PublishUpdatePrivacy::Public,
+                Duration::from_secs(10),
+            )
+            .await?;
+
+        let published = mock_hub.published();
+        assert_eq!(published.len(), 3);
+        assert_eq!(published[0].data(), Some("1"));
+        assert_eq!(published[0].privacy(), PublishUpdatePrivacy::Private);
+        assert_eq!(
+            published[0].id().map(ToString::to_string).as_deref(),
+            Some("urn:uuid:1")
+        );
+        assert_eq!(published[1].data(), Some("2"));
+        assert_eq!(published[1].r#type(), Some("BookUpdated"));
+        assert_eq!(published[2].data(), None);
+        assert_eq!(published[2].retry(), Some(Duration::from_secs(10)));
+        Ok(())
+    }
+}
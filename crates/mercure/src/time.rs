@@ -0,0 +1,76 @@
+//! Timers which work both with Tokio and in web browsers, where there is no
+//! Tokio runtime.
+
+use std::future::Future;
+use std::pin::pin;
+use std::time::Duration;
+
+use futures_util::future::{self, Either};
+
+/// Waits until the duration has elapsed.
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Waits until the duration has elapsed.
+///
+/// The timer is driven by `setTimeout` on the local task, as JavaScript values
+/// are `!Send`, so that the returned future is `Send`.
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub(crate) async fn sleep(duration: Duration) {
+    use tokio::sync::oneshot;
+    use wasm_bindgen::prelude::wasm_bindgen;
+    use wasm_bindgen_futures::JsFuture;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_name = setTimeout)]
+        fn set_timeout(handler: &js_sys::Function, timeout: i32);
+    }
+
+    let (elapsed, elapsed_receiver) = oneshot::channel();
+    wasm_bindgen_futures::spawn_local(async move {
+        let timeout = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+        let timer = js_sys::Promise::new(&mut |resolve, _reject| {
+            set_timeout(&resolve, timeout);
+        });
+        // The timer never rejects.
+        let _ = JsFuture::from(timer).await;
+        // The sleep may have been cancelled.
+        let _ = elapsed.send(());
+    });
+    // The local task never drops the sender without sending.
+    let _ = elapsed_receiver.await;
+}
+
+/// Waits for the future to complete, returning `None` if the duration elapses
+/// first.
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    match future::select(pin!(future), pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(((), _)) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn it_returns_output_before_timeout() {
+        assert_eq!(
+            timeout(Duration::from_secs(60), async { 42 }).await,
+            Some(42)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_times_out() {
+        let never = future::pending::<()>();
+        assert_eq!(timeout(Duration::from_millis(10), never).await, None);
+    }
+}
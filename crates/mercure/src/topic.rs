@@ -1,5 +1,10 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
 use std::iter::{self, Chain, FusedIterator};
 
+#[cfg(feature = "serde-errors")]
+use serde::ser::SerializeStruct as _;
 use serde::Serialize;
 use url::Url;
 
@@ -10,12 +15,47 @@ use url::Url;
 /// > considered to be the canonical IRI of the topic, and other ones are
 /// > considered to be alternate IRIs. The hub MUST dispatch this update to
 /// > subscribers that are subscribed to both canonical or alternate IRIs.
+///
+/// Topics are not required to be HTTP(S) URLs. Any absolute URL can be used,
+/// e.g. `urn:isbn:9780735218789`.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Topic {
     canonical_url: Url,
     alternate_urls: Vec<Url>,
 }
 
+/// A builder for a [`Topic`], created by [`Topic::builder`].
+#[derive(Clone, Debug)]
+#[must_use = "builders do nothing unless `build` is called"]
+pub struct TopicBuilder {
+    canonical_url: Url,
+    alternate_urls: Vec<Url>,
+}
+
+/// An error returned from [`Topic::try_new`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct InvalidTopicError {
+    kind: InvalidTopicErrorKind,
+    inner: Box<dyn Error + Send + Sync + 'static>,
+}
+
+/// The various types of errors that can cause [`Topic::try_new`] to fail.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
+#[non_exhaustive]
+pub enum InvalidTopicErrorKind {
+    /// The URL uses a scheme which does not identify a resource, e.g. `data:`.
+    UnsupportedScheme,
+    /// The URL has a hierarchical path but no host, e.g. `file:///books/1`.
+    MissingHost,
+}
+
+/// The error type returned when a conversion from [`Vec<Url>`] to [`Topic`]
+/// fails.
+#[derive(Debug)]
+pub struct TryFromVecError;
+
 /// An iterator over the URL(s) of a [`Topic`], created by [`iter`].
 ///
 /// [`iter`]: Topic::iter
@@ -23,6 +63,14 @@ pub struct Topic {
 #[must_use = "iterators are lazy and do nothing unless consumed"]
 pub struct Iter<'a>(Chain<iter::Once<&'a Url>, std::slice::Iter<'a, Url>>);
 
+/// An iterator over the URL(s) of a [`Topic`] as strings, created by
+/// [`as_strs`].
+///
+/// [`as_strs`]: Topic::as_strs
+#[derive(Clone, Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct AsStrs<'a>(Iter<'a>);
+
 /// An owning iterator over the URL(s) of a [`Topic`], created by [`into_iter`].
 ///
 /// [`into_iter`]: Topic::into_iter
@@ -39,6 +87,70 @@ impl From<Url> for Topic {
     }
 }
 
+impl TryFrom<Vec<Url>> for Topic {
+    type Error = TryFromVecError;
+
+    /// Performs the conversion.
+    ///
+    /// The first URL is the canonical URL, and the rest are alternate URLs.
+    /// Fails if there are no URLs.
+    fn try_from(mut urls: Vec<Url>) -> Result<Self, Self::Error> {
+        if urls.is_empty() {
+            return Err(TryFromVecError);
+        }
+        let canonical_url = urls.remove(0);
+
+        Ok(Self {
+            canonical_url,
+            alternate_urls: urls,
+        })
+    }
+}
+
+impl FromIterator<Url> for Topic {
+    /// Creates a `Topic` from an iterator.
+    ///
+    /// The first URL is the canonical URL, and the rest are alternate URLs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields no URLs. Use [`Topic::try_from`] with a
+    /// [`Vec<Url>`] to handle that case.
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = Url>,
+    {
+        let mut iter = iter.into_iter();
+        let canonical_url = iter.next().expect("iterator should yield at least one URL");
+
+        Self {
+            canonical_url,
+            alternate_urls: iter.collect(),
+        }
+    }
+}
+
+impl From<Topic> for Cow<'_, Topic> {
+    fn from(topic: Topic) -> Self {
+        Cow::Owned(topic)
+    }
+}
+
+impl<'a> From<&'a Topic> for Cow<'a, Topic> {
+    fn from(topic: &'a Topic) -> Self {
+        Cow::Borrowed(topic)
+    }
+}
+
+impl fmt::Display for Topic {
+    /// Formats the canonical URL of the `Topic`.
+    ///
+    /// Use [`Topic::iter`] to get all URLs, including alternate URLs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.canonical_url)
+    }
+}
+
 impl<'a> IntoIterator for &'a Topic {
     type IntoIter = Iter<'a>;
     type Item = &'a Url;
@@ -77,6 +189,70 @@ impl Topic {
         }
     }
 
+    /// Constructs a new `Topic`, checking that its URLs are suitable as topic
+    /// identifiers.
+    ///
+    /// A [`Url`] is always absolute, so relative URLs are already ruled out.
+    /// In addition, this rejects:
+    ///
+    /// - URLs with a `data:`, `blob:`, `javascript:`, or `about:` scheme,
+    ///   which do not identify a resource.
+    /// - URLs with a hierarchical path but no host, e.g. `file:///books/1`.
+    ///
+    /// URNs and other URLs without a hierarchical path, e.g.
+    /// `urn:isbn:9780735218789`, are accepted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::Topic;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let topic = Topic::try_new("https://example.com/books/1".parse()?, vec![
+    ///     "urn:isbn:9780735218789".parse()?,
+    /// ])?;
+    ///
+    /// assert!(Topic::try_new("data:text/plain,1".parse()?, vec![]).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_new(
+        canonical_url: Url,
+        alternate_urls: Vec<Url>,
+    ) -> Result<Self, InvalidTopicError> {
+        for url in iter::once(&canonical_url).chain(&alternate_urls) {
+            validate_url(url)?;
+        }
+
+        Ok(Self::new(canonical_url, alternate_urls))
+    }
+
+    /// Constructs a new [`TopicBuilder`] with the given canonical URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::Topic;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let topic = Topic::builder("https://example.com/books/1".parse()?)
+    ///     .alternate("https://example.com/users/1/books/1".parse()?)
+    ///     .build();
+    /// assert_eq!(topic.canonical_url().as_str(), "https://example.com/books/1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(canonical_url: Url) -> TopicBuilder {
+        TopicBuilder {
+            canonical_url,
+            alternate_urls: Default::default(),
+        }
+    }
+
     /// Returns an iterator over the `Topic`.
     ///
     /// The iterator yields the canonical URL, followed by alternate URLs (if
@@ -85,13 +261,175 @@ impl Topic {
         Iter(iter::once(&self.canonical_url).chain(self.alternate_urls.iter()))
     }
 
+    /// Returns an iterator over the `Topic` as strings, e.g. for logging.
+    ///
+    /// The iterator yields the serialized canonical URL, followed by alternate
+    /// URLs (if any), without allocating.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::Topic;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let topic = Topic::builder("https://example.com/books/1".parse()?)
+    ///     .alternate("https://example.com/users/1/books/1".parse()?)
+    ///     .build();
+    /// assert_eq!(topic.as_strs().collect::<Vec<_>>(), [
+    ///     "https://example.com/books/1",
+    ///     "https://example.com/users/1/books/1",
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn as_strs(&self) -> AsStrs<'_> {
+        AsStrs(self.iter())
+    }
+
     pub fn canonical_url(&self) -> &Url {
         &self.canonical_url
     }
 
-    pub fn alternate_urls(&self) -> &Vec<Url> {
+    pub fn alternate_urls(&self) -> &[Url] {
         &self.alternate_urls
     }
+
+    /// Returns the number of URLs of the `Topic`, i.e. the canonical URL and
+    /// alternate URLs (if any), which is always at least 1.
+    ///
+    /// This is the number of items yielded by [`Topic::iter`].
+    #[expect(
+        clippy::len_without_is_empty,
+        reason = "a `Topic` always has a canonical URL"
+    )]
+    pub fn len(&self) -> usize {
+        self.alternate_urls.len().saturating_add(1)
+    }
+
+    /// Returns `true` if the `Topic` has any alternate URLs.
+    pub fn has_alternates(&self) -> bool {
+        !self.alternate_urls.is_empty()
+    }
+
+    /// Returns `true` if the `Topic` has only its canonical URL.
+    pub fn is_single(&self) -> bool {
+        self.alternate_urls.is_empty()
+    }
+
+    /// Appends an alternate URL.
+    pub fn push_alternate(&mut self, alternate_url: Url) {
+        self.alternate_urls.push(alternate_url);
+    }
+}
+
+impl TopicBuilder {
+    /// Adds an alternate URL.
+    pub fn alternate(mut self, alternate_url: Url) -> Self {
+        self.alternate_urls.push(alternate_url);
+        self
+    }
+
+    /// Adds alternate URLs.
+    pub fn alternates<I>(mut self, alternate_urls: I) -> Self
+    where
+        I: IntoIterator<Item = Url>,
+    {
+        self.alternate_urls.extend(alternate_urls);
+        self
+    }
+
+    /// Builds the [`Topic`].
+    pub fn build(self) -> Topic {
+        Topic {
+            canonical_url: self.canonical_url,
+            alternate_urls: self.alternate_urls,
+        }
+    }
+}
+
+impl fmt::Display for InvalidTopicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            InvalidTopicErrorKind::UnsupportedScheme => {
+                write!(
+                    f,
+                    "the scheme of topic URL {url} does not identify a resource",
+                    url = self.inner
+                )
+            },
+            InvalidTopicErrorKind::MissingHost => {
+                write!(f, "topic URL {url} has no host", url = self.inner)
+            },
+        }
+    }
+}
+
+impl Error for InvalidTopicError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            InvalidTopicErrorKind::UnsupportedScheme | InvalidTopicErrorKind::MissingHost => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for InvalidTopicError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("InvalidTopicError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl InvalidTopicError {
+    /// Returns the corresponding [`InvalidTopicErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &InvalidTopicErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for TryFromVecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a topic must have at least one URL")
+    }
+}
+
+impl Error for TryFromVecError {}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for TryFromVecError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("TryFromVecError", 1)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+fn validate_url(url: &Url) -> Result<(), InvalidTopicError> {
+    if matches!(url.scheme(), "data" | "blob" | "javascript" | "about") {
+        return Err(InvalidTopicError {
+            kind: InvalidTopicErrorKind::UnsupportedScheme,
+            inner: url.to_string().into(),
+        });
+    }
+    if !url.cannot_be_a_base() && url.host().is_none() {
+        return Err(InvalidTopicError {
+            kind: InvalidTopicErrorKind::MissingHost,
+            inner: url.to_string().into(),
+        });
+    }
+
+    Ok(())
 }
 
 impl<'a> Iterator for Iter<'a> {
@@ -168,6 +506,40 @@ impl<'a> FusedIterator for Iter<'a> {}
 
 impl<'a> ExactSizeIterator for Iter<'a> {}
 
+impl<'a> Iterator for AsStrs<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Url::as_str)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.0.count()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.0.nth(n).map(Url::as_str)
+    }
+}
+
+impl<'a> DoubleEndedIterator for AsStrs<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(Url::as_str)
+    }
+}
+
+impl<'a> FusedIterator for AsStrs<'a> {}
+
+impl<'a> ExactSizeIterator for AsStrs<'a> {}
+
 impl Iterator for IntoIter {
     type Item = Url;
 
@@ -241,3 +613,130 @@ impl DoubleEndedIterator for IntoIter {
 impl FusedIterator for IntoIter {}
 
 impl ExactSizeIterator for IntoIter {}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context as _, Result};
+
+    use super::*;
+
+    #[test]
+    fn it_builds_topic_with_alternate_urls() -> Result<()> {
+        let canonical_url: Url = "https://example.com/books/1".parse()?;
+        let alternate_urls: Vec<Url> = vec![
+            "https://example.com/users/1/books/1".parse()?,
+            "urn:isbn:9780735218789".parse()?,
+        ];
+        let topic = Topic::new(canonical_url.clone(), alternate_urls.clone());
+
+        let built_topic = Topic::builder(canonical_url.clone())
+            .alternate(alternate_urls[0].clone())
+            .alternates(alternate_urls[1..].iter().cloned())
+            .build();
+        assert_eq!(built_topic, topic);
+        assert_eq!(
+            serde_json::to_string(&built_topic)?,
+            serde_json::to_string(&topic)?
+        );
+
+        let mut pushed_topic = Topic::from(canonical_url);
+        for alternate_url in alternate_urls {
+            pushed_topic.push_alternate(alternate_url);
+        }
+        assert_eq!(pushed_topic, topic);
+        Ok(())
+    }
+
+    #[test]
+    fn it_counts_topic_urls() -> Result<()> {
+        let topic = Topic::new("https://example.com/books/1".parse()?, vec![]);
+        assert_eq!(topic.len(), 1);
+        assert!(topic.is_single());
+        assert!(!topic.has_alternates());
+
+        let topic = Topic::builder("https://example.com/books/1".parse()?)
+            .alternate("https://example.com/users/1/books/1".parse()?)
+            .alternate("https://example.com/users/2/books/1".parse()?)
+            .build();
+        assert_eq!(topic.len(), 3);
+        assert_eq!(topic.len(), topic.iter().count());
+        assert!(!topic.is_single());
+        assert!(topic.has_alternates());
+        Ok(())
+    }
+
+    #[test]
+    fn it_displays_canonical_url() -> Result<()> {
+        let topic = Topic::builder("https://example.com/books/1".parse()?)
+            .alternate("urn:isbn:9780735218789".parse()?)
+            .build();
+        assert_eq!(topic.to_string(), "https://example.com/books/1");
+        assert_eq!(
+            serde_json::to_string(&topic)?,
+            r#"["https://example.com/books/1","urn:isbn:9780735218789"]"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_iterates_topic_urls_as_strs() -> Result<()> {
+        let topic = Topic::builder("https://example.com/books/1".parse()?)
+            .alternate("urn:isbn:9780735218789".parse()?)
+            .build();
+        let mut strs = topic.as_strs();
+        assert_eq!(strs.len(), 2);
+        assert_eq!(strs.next_back(), Some("urn:isbn:9780735218789"));
+        assert_eq!(strs.next(), Some("https://example.com/books/1"));
+        assert_eq!(strs.next(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_collects_topic_with_canonical_url_first() -> Result<()> {
+        let urls: Vec<Url> = vec![
+            "https://example.com/books/1".parse()?,
+            "urn:isbn:9780735218789".parse()?,
+        ];
+        let topic: Topic = urls.iter().cloned().collect();
+        assert_eq!(topic.canonical_url(), &urls[0]);
+        assert_eq!(topic.alternate_urls(), &urls[1..]);
+        assert_eq!(Topic::try_from(urls)?, topic);
+        assert!(Topic::try_from(Vec::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "iterator should yield at least one URL")]
+    fn it_panics_collecting_topic_from_empty_iterator() {
+        let _: Topic = iter::empty().collect();
+    }
+
+    #[test]
+    fn it_validates_topic_urls() -> Result<()> {
+        assert!(Topic::try_new(
+            "https://example.com/books/1".parse()?,
+            vec![
+                "urn:isbn:9780735218789".parse()?,
+                "mailto:books@example.com".parse()?,
+            ]
+        )
+        .is_ok());
+
+        let err = Topic::try_new(
+            "https://example.com/books/1".parse()?,
+            vec!["data:text/plain,1".parse()?],
+        )
+        .err()
+        .context("data URL should be rejected")?;
+        assert!(matches!(
+            err.kind(),
+            InvalidTopicErrorKind::UnsupportedScheme
+        ));
+
+        let err = Topic::try_new("file:///books/1".parse()?, vec![])
+            .err()
+            .context("URL without host should be rejected")?;
+        assert!(matches!(err.kind(), InvalidTopicErrorKind::MissingHost));
+        Ok(())
+    }
+}
@@ -1,8 +1,21 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::OnceLock;
 
+#[cfg(feature = "serde-errors")]
+use serde::ser::SerializeStruct as _;
 use serde::{Deserialize, Serialize};
-use uri_template_system::Template;
+use uri_template_system::{Template, Value, Values};
+use url::{Position, Url};
+
+use self::matcher::Matcher;
+
+mod matcher;
 
 /// [The Mercure Protocol, Section 3](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-3)
 ///
@@ -34,6 +47,22 @@ pub enum TopicSelector {
     UriTemplate(UriTemplate),
 }
 
+/// A rule which matches topics matched by any of its allowed topic selectors,
+/// except topics matched by any of its denied topic selectors.
+///
+/// # Note
+///
+/// This is a client-side construct, e.g. for simulating authorization. The
+/// Mercure protocol has no notion of denied topic selectors: a [JWT] can only
+/// carry allowed topic selectors.
+///
+/// [JWT]: https://datatracker.ietf.org/doc/html/rfc7519
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SelectorRule {
+    allow: Vec<TopicSelector>,
+    deny: Vec<TopicSelector>,
+}
+
 /// A [URI Template].
 ///
 /// [URI Template]: https://datatracker.ietf.org/doc/html/rfc6570
@@ -50,9 +79,44 @@ pub enum TopicSelector {
 /// [URL]: https://url.spec.whatwg.org/
 ///
 /// [^abs]: <https://github.com/dunglas/mercure/issues/947#issuecomment-2324959856>
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
-#[serde(transparent)]
-pub struct UriTemplate(String);
+///
+/// The matcher used by [`TopicSelector::matches`] is compiled once and
+/// cached.
+///
+/// # Equality
+///
+/// Equality, ordering and hashing are textual, i.e. they only consider the
+/// template string, not what it matches. For example, `{+path}` and `{+rest}`
+/// compare unequal, although they match the same topics.
+///
+/// They are consistent with those of `str`, so a `UriTemplate` used as a key
+/// in a map can be looked up by `&str`, thanks to `Borrow<str>`.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct UriTemplate {
+    template: String,
+    /// The compiled matcher, which is only compiled once when first needed.
+    matcher: OnceLock<Matcher>,
+}
+
+/// An error returned from [`UriTemplate::expand`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ExpandError {
+    kind: ExpandErrorKind,
+    inner: Box<dyn Error + Send + Sync + 'static>,
+}
+
+/// The various types of errors that can cause [`UriTemplate::expand`] to fail.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
+#[non_exhaustive]
+pub enum ExpandErrorKind {
+    /// Failed to expand URI Template.
+    Expand,
+    /// The expanded URI Template is not a valid absolute URL.
+    ParseUrl,
+}
 
 /// An error which can be returned when parsing a [`UriTemplate`].
 #[derive(Debug)]
@@ -61,6 +125,216 @@ pub struct ParseUriTemplateError {
     inner: uri_template_system::ParseError,
 }
 
+impl TopicSelector {
+    /// Returns `true` if the given topic is matched by this `TopicSelector`.
+    ///
+    /// A [`TopicSelector::Wildcard`] matches all topics. A
+    /// [`TopicSelector::UriTemplate`] matches a topic if the topic is a
+    /// possible expansion of the [URI Template].
+    ///
+    /// [URI Template]: https://datatracker.ietf.org/doc/html/rfc6570
+    ///
+    /// # Matching semantics
+    ///
+    /// The [URI Template] must match the whole topic, in its serialized form
+    /// (e.g. `https://example.com` is serialized as `https://example.com/`).
+    ///
+    /// - Literals must match exactly, so trailing slashes are significant:
+    ///   `https://example.com/books/{book_id}` does not match
+    ///   `https://example.com/books/1/`.
+    /// - Simple expressions (e.g. `{book_id}`) match within a single path
+    ///   segment, as their values cannot contain reserved characters such as
+    ///   `/` unless percent-encoded.
+    /// - Reserved (`{+path}`) and fragment (`{#fragment}`) expressions are
+    ///   greedy and match across path segments.
+    /// - Path segment expressions (e.g. `{/rest*}`) match any number of path
+    ///   segments.
+    /// - Variables may be undefined or empty, so
+    ///   `https://example.com/books/{book_id}` matches
+    ///   `https://example.com/books/`.
+    /// - Percent-encoding is normalized in both the [URI Template] and the
+    ///   topic: percent-encoded unreserved characters (e.g. `%7E`) are decoded,
+    ///   and other percent-encoded octets are uppercased (e.g. `%2f` is the
+    ///   same as `%2F`), as per [RFC 3986, Section 6.2.2].
+    /// - Query parameters are matched in any order, if the query of the [URI
+    ///   Template] consists of `name=value` pairs (whose names and values may
+    ///   contain simple expressions, e.g. `category={category}`) and form-style
+    ///   query expressions (e.g. `{?category}` or `{&page}`). Each pair must
+    ///   match a distinct query parameter of the topic, and the other query
+    ///   parameters must be named by a form-style query expression. Each
+    ///   variable of a form-style query expression matches at most one query
+    ///   parameter, unless it has the explode modifier (e.g. `{?category*}`),
+    ///   so `{?category}` does not match `?category=a&category=b`. Otherwise,
+    ///   e.g. if the query may be expanded from a reserved expression (e.g.
+    ///   `{+path}`), the query is matched literally.
+    ///
+    /// [RFC 3986, Section 6.2.2]: https://datatracker.ietf.org/doc/html/rfc3986#section-6.2.2
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::TopicSelector;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let topic_selector =
+    ///     TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+    /// assert!(topic_selector.matches(&"https://example.com/books/1".parse()?));
+    /// assert!(!topic_selector.matches(&"https://example.com/users/1".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn matches(&self, topic: &Url) -> bool {
+        match self {
+            Self::Wildcard => true,
+            Self::UriTemplate(uri_template) => uri_template.matcher().is_match(topic.as_str()),
+        }
+    }
+
+    /// Returns `true` if this `TopicSelector` authorizes the other topic
+    /// selector, i.e. every topic matched by the other topic selector is
+    /// matched by this one.
+    ///
+    /// Deciding this for arbitrary [URI Template]s is not feasible, so the
+    /// rules are conservative, and may return `false` even though every topic
+    /// matched by the other topic selector is matched by this one:
+    ///
+    /// - [`TopicSelector::Wildcard`] authorizes any topic selector.
+    /// - A [`TopicSelector::UriTemplate`] does not authorize
+    ///   [`TopicSelector::Wildcard`].
+    /// - A [`TopicSelector::UriTemplate`] authorizes an identical [URI
+    ///   Template].
+    /// - A [`TopicSelector::UriTemplate`] authorizes a [URI Template] without
+    ///   expressions (i.e. a single topic) if it [matches] that topic.
+    ///
+    /// [URI Template]: https://datatracker.ietf.org/doc/html/rfc6570
+    /// [matches]: TopicSelector::matches
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::TopicSelector;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let books = TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+    /// let book = TopicSelector::UriTemplate("https://example.com/books/1".try_into()?);
+    /// assert!(TopicSelector::Wildcard.authorizes(&books));
+    /// assert!(books.authorizes(&books));
+    /// assert!(books.authorizes(&book));
+    /// assert!(!book.authorizes(&books));
+    /// assert!(!books.authorizes(&TopicSelector::Wildcard));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn authorizes(&self, other: &TopicSelector) -> bool {
+        match (self, other) {
+            (Self::Wildcard, _) => true,
+            (Self::UriTemplate(_), Self::Wildcard) => false,
+            (Self::UriTemplate(uri_template), Self::UriTemplate(other_uri_template)) => {
+                uri_template == other_uri_template
+                    || (!other_uri_template.template.contains('{')
+                        && other_uri_template
+                            .template
+                            .parse::<Url>()
+                            .is_ok_and(|topic| self.matches(&topic)))
+            },
+        }
+    }
+
+    /// Returns the union of the given sets of topic selectors.
+    ///
+    /// The result is normalized: if any of the sets contains
+    /// [`TopicSelector::Wildcard`], the result only contains
+    /// [`TopicSelector::Wildcard`]; otherwise duplicates are removed, keeping
+    /// the first occurrence of each topic selector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::jwt::PublisherJwtSecret;
+    /// use mercure::{PublisherJwt, TopicSelector};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let books = TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+    /// let users = TopicSelector::UriTemplate("https://example.com/users/{user_id}".try_into()?);
+    /// let editor_topic_selectors = vec![books.clone()];
+    /// let admin_topic_selectors = vec![books.clone(), users.clone()];
+    ///
+    /// let topic_selectors = TopicSelector::union([editor_topic_selectors, admin_topic_selectors]);
+    /// assert_eq!(topic_selectors, [books, users]);
+    ///
+    /// let publisher_jwt_secret =
+    ///     PublisherJwtSecret::from(b"!ChangeThisMercureHubJWTSecretKey!".to_vec());
+    /// let publisher_jwt = PublisherJwt::new(&publisher_jwt_secret, topic_selectors)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn union<I>(topic_selector_sets: I) -> Vec<Self>
+    where
+        I: IntoIterator,
+        I::Item: IntoIterator<Item = Self>,
+    {
+        let mut seen = HashSet::new();
+        let mut topic_selectors = Vec::new();
+        for topic_selector in topic_selector_sets.into_iter().flatten() {
+            if topic_selector == Self::Wildcard {
+                return vec![Self::Wildcard];
+            }
+            if seen.insert(topic_selector.clone()) {
+                topic_selectors.push(topic_selector);
+            }
+        }
+
+        topic_selectors
+    }
+
+    /// Constructs a `TopicSelector` which matches the given base URL and any
+    /// path beneath it.
+    ///
+    /// The generated [URI Template] is the base URL with its query and
+    /// fragment removed, and with any trailing slash removed, followed by the
+    /// `{/rest*}` path segment expansion. For example, the base URL
+    /// `https://example.com/users/1` (or `https://example.com/users/1/`)
+    /// results in the [URI Template] `https://example.com/users/1{/rest*}`,
+    /// which matches `https://example.com/users/1`,
+    /// `https://example.com/users/1/books` and
+    /// `https://example.com/users/1/books/1`, but not
+    /// `https://example.com/users/10`.
+    ///
+    /// [URI Template]: https://datatracker.ietf.org/doc/html/rfc6570
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path of the base URL contains characters which
+    /// are not allowed in a [URI Template] literal (e.g. `|`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::TopicSelector;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let topic_selector = TopicSelector::prefix(&"https://example.com/users/1".parse()?)?;
+    /// assert_eq!(topic_selector.to_string(), "https://example.com/users/1{/rest*}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prefix(base: &Url) -> Result<Self, ParseUriTemplateError> {
+        let base = base[..Position::AfterPath].trim_end_matches('/');
+
+        Ok(Self::UriTemplate(
+            format!("{base}{{/rest*}}").as_str().try_into()?,
+        ))
+    }
+}
+
 impl fmt::Display for TopicSelector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -70,6 +344,84 @@ impl fmt::Display for TopicSelector {
     }
 }
 
+impl FromStr for TopicSelector {
+    type Err = ParseUriTemplateError;
+
+    /// Parses `*` as [`TopicSelector::Wildcard`], and anything else as
+    /// [`TopicSelector::UriTemplate`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::TopicSelector;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// assert_eq!("*".parse::<TopicSelector>()?, TopicSelector::Wildcard);
+    /// assert_eq!(
+    ///     "https://example.com/books/{book_id}".parse::<TopicSelector>()?,
+    ///     TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "*" {
+            return Ok(Self::Wildcard);
+        }
+
+        Ok(Self::UriTemplate(s.try_into()?))
+    }
+}
+
+impl SelectorRule {
+    /// Constructs a new `SelectorRule`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::topic_selector::SelectorRule;
+    /// use mercure::TopicSelector;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let selector_rule = SelectorRule::new(vec![TopicSelector::Wildcard], vec![
+    ///     TopicSelector::UriTemplate("https://example.com/admin{/rest*}".try_into()?),
+    /// ]);
+    /// assert!(selector_rule.matches(&"https://example.com/books/1".parse()?));
+    /// assert!(!selector_rule.matches(&"https://example.com/admin/users/1".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(allow: Vec<TopicSelector>, deny: Vec<TopicSelector>) -> Self {
+        Self { allow, deny }
+    }
+
+    /// Returns the allowed topic selectors.
+    pub fn allow(&self) -> &[TopicSelector] {
+        &self.allow
+    }
+
+    /// Returns the denied topic selectors.
+    pub fn deny(&self) -> &[TopicSelector] {
+        &self.deny
+    }
+
+    /// Returns `true` if the given topic is matched by any of the allowed
+    /// topic selectors, and not matched by any of the denied topic selectors.
+    pub fn matches(&self, topic: &Url) -> bool {
+        self.allow
+            .iter()
+            .any(|topic_selector| topic_selector.matches(topic))
+            && !self
+                .deny
+                .iter()
+                .any(|topic_selector| topic_selector.matches(topic))
+    }
+}
+
 impl<'a> TryFrom<&'a str> for UriTemplate {
     type Error = ParseUriTemplateError;
 
@@ -90,13 +442,135 @@ impl<'a> TryFrom<&'a str> for UriTemplate {
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
         let _template = Template::parse(s).map_err(|err| Self::Error { inner: err })?;
 
-        Ok(Self(s.to_owned()))
+        Ok(Self {
+            template: s.to_owned(),
+            matcher: OnceLock::new(),
+        })
+    }
+}
+
+impl TryFrom<String> for UriTemplate {
+    type Error = ParseUriTemplateError;
+
+    /// Performs the conversion.
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::try_from(s.as_str())
+    }
+}
+
+impl UriTemplate {
+    /// Expands this [URI Template] with the given variables into a URL, e.g.
+    /// the topic to publish an update to.
+    ///
+    /// [URI Template]: https://datatracker.ietf.org/doc/html/rfc6570
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expansion is not a valid absolute URL, e.g.
+    /// because the [URI Template] is not in absolute form.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// use mercure::topic_selector::UriTemplate;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let uri_template = UriTemplate::try_from("https://example.com/books/{book_id}")?;
+    /// let topic = uri_template.expand(&[("book_id", "1")])?;
+    /// assert_eq!(topic.as_str(), "https://example.com/books/1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn expand(&self, variables: &[(&str, &str)]) -> Result<Url, ExpandError> {
+        let template = Template::parse(&self.template)
+            .expect("`self.template` should be a valid URI Template");
+        let values = variables
+            .iter()
+            .fold(Values::default(), |values, &(name, value)| {
+                values.add(name, Value::item(value))
+            });
+        let expanded = template.expand(&values).map_err(|err| ExpandError {
+            kind: ExpandErrorKind::Expand,
+            inner: err.into(),
+        })?;
+
+        expanded
+            .parse()
+            .map_err(|err: url::ParseError| ExpandError {
+                kind: ExpandErrorKind::ParseUrl,
+                inner: err.into(),
+            })
+    }
+
+    /// Returns the template string.
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    fn matcher(&self) -> &Matcher {
+        self.matcher
+            .get_or_init(|| matcher::compile(&self.template))
+    }
+}
+
+impl PartialEq for UriTemplate {
+    fn eq(&self, other: &Self) -> bool {
+        self.template == other.template
+    }
+}
+
+impl Eq for UriTemplate {}
+
+impl PartialOrd for UriTemplate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UriTemplate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.template.cmp(&other.template)
+    }
+}
+
+impl Hash for UriTemplate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.template.hash(state);
+    }
+}
+
+impl AsRef<str> for UriTemplate {
+    fn as_ref(&self) -> &str {
+        &self.template
+    }
+}
+
+impl Borrow<str> for UriTemplate {
+    fn borrow(&self) -> &str {
+        &self.template
+    }
+}
+
+impl Serialize for UriTemplate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.template.serialize(serializer)
+    }
+}
+
+impl fmt::Debug for UriTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UriTemplate").field(&self.template).finish()
     }
 }
 
 impl fmt::Display for UriTemplate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt(&self.0, f)
+        fmt::Display::fmt(&self.template, f)
     }
 }
 
@@ -111,3 +585,426 @@ impl Error for ParseUriTemplateError {
         Some(&self.inner)
     }
 }
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for ParseUriTemplateError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ParseUriTemplateError", 1)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ExpandErrorKind::Expand => {
+                let err = self
+                    .inner
+                    .downcast_ref::<uri_template_system::ExpandError>()
+                    .unwrap();
+                write!(f, "failed to expand URI Template: {err}")
+            },
+            ExpandErrorKind::ParseUrl => {
+                let err = self.inner.downcast_ref::<url::ParseError>().unwrap();
+                write!(f, "expanded URI Template is not a valid URL: {err}")
+            },
+        }
+    }
+}
+
+impl Error for ExpandError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            ExpandErrorKind::Expand => {
+                let err = self
+                    .inner
+                    .downcast_ref::<uri_template_system::ExpandError>()
+                    .unwrap();
+                Some(err)
+            },
+            ExpandErrorKind::ParseUrl => {
+                let err = self.inner.downcast_ref::<url::ParseError>().unwrap();
+                Some(err)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for ExpandError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ExpandError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl ExpandError {
+    /// Returns the corresponding [`ExpandErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &ExpandErrorKind {
+        &self.kind
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context as _, Result};
+
+    use super::*;
+
+    #[test]
+    fn it_unions_topic_selectors_without_duplicates() -> Result<()> {
+        let books = TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+        let users = TopicSelector::UriTemplate("https://example.com/users/{user_id}".try_into()?);
+        let topic_selectors = TopicSelector::union([
+            vec![users.clone(), books.clone()],
+            vec![books.clone(), users.clone()],
+        ]);
+        assert_eq!(topic_selectors, [users, books]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_unions_topic_selectors_to_wildcard() -> Result<()> {
+        let books = TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+        let topic_selectors = TopicSelector::union([vec![books], vec![TopicSelector::Wildcard]]);
+        assert_eq!(topic_selectors, [TopicSelector::Wildcard]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_topic_with_selector_rule_deny_over_allow() -> Result<()> {
+        let selector_rule = SelectorRule::new(
+            vec![TopicSelector::UriTemplate(
+                "https://example.com/books/{book_id}".try_into()?,
+            )],
+            vec![TopicSelector::UriTemplate(
+                "https://example.com/books/1".try_into()?,
+            )],
+        );
+        assert!(selector_rule.matches(&"https://example.com/books/2".parse()?));
+        assert!(!selector_rule.matches(&"https://example.com/books/1".parse()?));
+        assert!(!selector_rule.matches(&"https://example.com/users/1".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_creates_prefix_topic_selector() -> Result<()> {
+        let topic_selector = TopicSelector::prefix(&"https://example.com/users/1".parse()?)?;
+        assert_eq!(
+            topic_selector.to_string(),
+            "https://example.com/users/1{/rest*}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_creates_prefix_topic_selector_without_trailing_slash_query_or_fragment() -> Result<()> {
+        let topic_selector =
+            TopicSelector::prefix(&"https://example.com/users/1/?page=1#books".parse()?)?;
+        assert_eq!(
+            topic_selector.to_string(),
+            "https://example.com/users/1{/rest*}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_any_topic_with_wildcard() -> Result<()> {
+        assert!(TopicSelector::Wildcard.matches(&"https://example.com/books/1".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_topic_with_uri_template() -> Result<()> {
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books/1".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books/1/reviews".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/users/1".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_topic_with_uri_template_without_expressions() -> Result<()> {
+        let topic_selector = TopicSelector::UriTemplate("https://example.com/books/1".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books/1".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books/10".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_topic_with_reserved_expansion() -> Result<()> {
+        let topic_selector = TopicSelector::UriTemplate("https://example.com/{+path}".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books/1/reviews".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_urn_topic_with_uri_template() -> Result<()> {
+        let topic_selector = TopicSelector::UriTemplate("urn:isbn:{isbn}".try_into()?);
+        assert!(topic_selector.matches(&"urn:isbn:9780735218789".parse()?));
+        assert!(!topic_selector.matches(&"urn:issn:0317-8471".parse()?));
+        assert!(TopicSelector::Wildcard.matches(&"urn:isbn:9780735218789".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_subpaths_with_prefix_topic_selector() -> Result<()> {
+        let topic_selector = TopicSelector::prefix(&"https://example.com/users/1".parse()?)?;
+        assert!(topic_selector.matches(&"https://example.com/users/1".parse()?));
+        assert!(topic_selector.matches(&"https://example.com/users/1/".parse()?));
+        assert!(topic_selector.matches(&"https://example.com/users/1/books/1".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/users/10".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/users/1?page=1".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_expands_prefix_topic_selector_to_subpaths() -> Result<()> {
+        let topic_selector = TopicSelector::prefix(&"https://example.com/users/1".parse()?)?;
+        let template = topic_selector.to_string();
+        let template = Template::parse(&template)?;
+        assert_eq!(
+            template.expand(&Values::default())?,
+            "https://example.com/users/1"
+        );
+        assert_eq!(
+            template.expand(&Values::default().add("rest", Value::list(["books", "1"])))?,
+            "https://example.com/users/1/books/1"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_trailing_slash_literally() -> Result<()> {
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+        assert!(!topic_selector.matches(&"https://example.com/books/1/".parse()?));
+        assert!(topic_selector.matches(&"https://example.com/books/".parse()?));
+
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books/{book_id}/".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books/1/".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books/1".parse()?));
+
+        let topic_selector = TopicSelector::UriTemplate("https://example.com".try_into()?);
+        assert!(!topic_selector.matches(&"https://example.com".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_across_segments_only_with_greedy_expressions() -> Result<()> {
+        let topic_selector = TopicSelector::UriTemplate("https://example.com/{path}".try_into()?);
+        assert!(!topic_selector.matches(&"https://example.com/books/1".parse()?));
+        assert!(topic_selector.matches(&"https://example.com/books%2F1".parse()?));
+
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books{/segments*}".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books/1/reviews/2".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books/1?page=2".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_query_parameters_in_any_order() -> Result<()> {
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books{?category,page}".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books".parse()?));
+        assert!(topic_selector.matches(&"https://example.com/books?category=fiction".parse()?));
+        assert!(
+            topic_selector.matches(&"https://example.com/books?page=2&category=fiction".parse()?)
+        );
+        assert!(!topic_selector.matches(&"https://example.com/books?sort=title".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books/1?page=2".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books?page=2#top".parse()?));
+
+        let topic_selector = TopicSelector::UriTemplate(
+            "https://example.com/books?lang=en&category={category}{&page}".try_into()?,
+        );
+        assert!(
+            topic_selector.matches(&"https://example.com/books?category=fiction&lang=en".parse()?)
+        );
+        assert!(topic_selector
+            .matches(&"https://example.com/books?page=2&category=fiction&lang=en".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books?category=fiction".parse()?));
+        assert!(
+            !topic_selector.matches(&"https://example.com/books?category=fiction&lang=fr".parse()?)
+        );
+        assert!(!topic_selector
+            .matches(&"https://example.com/books?category=science/fiction&lang=en".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_exploded_query_expression_with_any_names() -> Result<()> {
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books{?filters*}".try_into()?);
+        assert!(
+            topic_selector.matches(&"https://example.com/books?lang=en&category=fiction".parse()?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_match_repeated_query_parameters() -> Result<()> {
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books{?category}".try_into()?);
+        assert!(
+            !topic_selector.matches(&"https://example.com/books?category=a&category=b".parse()?)
+        );
+
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books{?a,b}".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books?b=3&a=1".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books?a=1&a=2&b=3".parse()?));
+
+        let topic_selector = TopicSelector::UriTemplate(
+            "https://example.com/books{?category}{&category}".try_into()?,
+        );
+        assert!(topic_selector.matches(&"https://example.com/books?category=a&category=b".parse()?));
+
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/books{?category*}".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books?category=a&category=b".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_matches_query_literally_after_reserved_expression() -> Result<()> {
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/{+path}?lang=en".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/books/1?lang=en".parse()?));
+        assert!(!topic_selector.matches(&"https://example.com/books/1?page=2&lang=en".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_normalizes_percent_encoding() -> Result<()> {
+        let topic_selector =
+            TopicSelector::UriTemplate("https://example.com/~users/{user_id}".try_into()?);
+        assert!(topic_selector.matches(&"https://example.com/%7Eusers/1".parse()?));
+        assert!(topic_selector.matches(&"https://example.com/%7eusers/a%2fb".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_topic_selector() -> Result<()> {
+        assert_eq!("*".parse::<TopicSelector>()?, TopicSelector::Wildcard);
+
+        let topic_selector = "https://example.com/books/{book_id}".parse::<TopicSelector>()?;
+        assert_eq!(
+            topic_selector,
+            TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?)
+        );
+        assert_eq!(
+            topic_selector.to_string().parse::<TopicSelector>()?,
+            topic_selector
+        );
+
+        assert!("https://example.com/books/{book_id"
+            .parse::<TopicSelector>()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_expands_uri_template() -> Result<()> {
+        let uri_template = UriTemplate::try_from("https://example.com/books/{book_id}{?page}")?;
+        assert_eq!(
+            uri_template
+                .expand(&[("book_id", "1/2"), ("page", "3")])?
+                .as_str(),
+            "https://example.com/books/1%2F2?page=3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_to_expand_relative_uri_template() -> Result<()> {
+        let uri_template = UriTemplate::try_from("/books/{book_id}")?;
+        let err = uri_template
+            .expand(&[("book_id", "1")])
+            .err()
+            .context("relative URI Template should not expand to a URL")?;
+        assert!(matches!(err.kind(), ExpandErrorKind::ParseUrl));
+        Ok(())
+    }
+
+    #[test]
+    fn it_compares_uri_templates_by_string() -> Result<()> {
+        let uri_template = UriTemplate::try_from("https://example.com/books/{book_id}")?;
+        let compiled = uri_template.clone();
+        assert!(TopicSelector::UriTemplate(compiled.clone())
+            .matches(&"https://example.com/books/1".parse()?));
+        assert_eq!(compiled, uri_template);
+        assert_eq!(
+            format!("{compiled:?}"),
+            r#"UriTemplate("https://example.com/books/{book_id}")"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_looks_up_uri_template_by_str() -> Result<()> {
+        use std::collections::{BTreeSet, HashMap};
+
+        let uri_template = UriTemplate::try_from("https://example.com/books/{book_id}")?;
+        assert_eq!(uri_template.as_str(), "https://example.com/books/{book_id}");
+
+        let mut names = HashMap::new();
+        names.insert(uri_template.clone(), "books");
+        assert_eq!(
+            names.get("https://example.com/books/{book_id}"),
+            Some(&"books")
+        );
+        assert_eq!(names.get("https://example.com/books/{id}"), None);
+
+        let uri_templates = BTreeSet::from([uri_template]);
+        assert!(uri_templates.contains("https://example.com/books/{book_id}"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_authorizes_topic_selectors_conservatively() -> Result<()> {
+        let books = TopicSelector::UriTemplate("https://example.com/books/{book_id}".try_into()?);
+        let book = TopicSelector::UriTemplate("https://example.com/books/1".try_into()?);
+        let user_books =
+            TopicSelector::UriTemplate("https://example.com/books/{book_id}{?user_id}".try_into()?);
+        let relative_book = TopicSelector::UriTemplate("/books/1".try_into()?);
+
+        assert!(TopicSelector::Wildcard.authorizes(&TopicSelector::Wildcard));
+        assert!(TopicSelector::Wildcard.authorizes(&books));
+        assert!(!books.authorizes(&TopicSelector::Wildcard));
+        assert!(books.authorizes(&books));
+        assert!(books.authorizes(&book));
+        assert!(!book.authorizes(&books));
+        assert!(!books.authorizes(&user_books));
+        assert!(!books.authorizes(&relative_book));
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_to_deserialize_invalid_uri_template() -> Result<()> {
+        assert!(serde_json::from_str::<TopicSelector>(r#""https://example.com/{abc""#).is_err());
+        assert!(serde_json::from_str::<UriTemplate>(r#""https://example.com/{abc""#).is_err());
+
+        let topic_selector: TopicSelector =
+            serde_json::from_str(r#""https://example.com/books/{book_id}""#)?;
+        assert!(topic_selector.matches(&"https://example.com/books/1".parse()?));
+        assert_eq!(
+            serde_json::to_string(&topic_selector)?,
+            r#""https://example.com/books/{book_id}""#
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+use std::borrow::Cow;
+use std::fmt::Write as _;
+
+use regex::Regex;
+
+/// [RFC 6570, Section 1.5](https://datatracker.ietf.org/doc/html/rfc6570#section-1.5)
+///
+/// > unreserved    =  ALPHA / DIGIT / "-" / "." / "_" / "~"
+const UNRESERVED: &str = r"A-Za-z0-9\-._~";
+
+/// [RFC 6570, Section 1.5](https://datatracker.ietf.org/doc/html/rfc6570#section-1.5)
+///
+/// > reserved      =  gen-delims / sub-delims
+/// > gen-delims    =  ":" / "/" / "?" / "#" / "[" / "]" / "@"
+/// > sub-delims    =  "!" / "$" / "&" / "'" / "(" / ")"
+/// >               /  "*" / "+" / "," / ";" / "="
+const RESERVED: &str = r":/?#\[\]@!$&'()*+,;=";
+
+/// [RFC 6570, Appendix A](https://datatracker.ietf.org/doc/html/rfc6570#appendix-A)
+struct Operator {
+    first: &'static str,
+    sep: &'static str,
+    named: bool,
+    allow_reserved: bool,
+}
+
+/// A compiled [URI Template], which matches all of its possible expansions.
+///
+/// [URI Template]: https://datatracker.ietf.org/doc/html/rfc6570
+#[derive(Clone)]
+pub(super) enum Matcher {
+    /// Matches the whole topic.
+    Regex(Regex),
+    /// Matches the topic without its query against `path`, and each of its
+    /// query parameters separately, so that they can be in any order.
+    ///
+    /// Each of `params` must match a distinct query parameter, and each of the
+    /// other query parameters must match one of `varspecs`. A varspec without
+    /// the explode modifier expands to at most one query parameter, so it
+    /// matches at most one of them.
+    Query {
+        path: Regex,
+        params: Vec<Regex>,
+        varspecs: Vec<QueryVarspec>,
+    },
+}
+
+/// A varspec of a form-style query expression, e.g. `category` in
+/// `{?category,page}`.
+#[derive(Clone)]
+pub(super) struct QueryVarspec {
+    /// Matches a single query parameter, prefixed with `&`.
+    regex: Regex,
+    explode: bool,
+}
+
+#[derive(Copy, Clone)]
+enum Token<'a> {
+    Literal(&'a str),
+    Expression(&'a str),
+}
+
+/// Compiles a (valid) [URI Template] into a [`Matcher`].
+///
+/// Each variable may be undefined, and each value may be empty. Values of
+/// expressions without the reserved (`+`) or fragment (`#`) operator cannot
+/// contain reserved characters (e.g. `/`), unless they are percent-encoded.
+///
+/// If the query of the [URI Template] consists of `name=value` pairs (whose
+/// names and values may contain simple expressions, e.g. `{category}`), and
+/// form-style query expressions (e.g. `{?category}` or `{&page}`), the query
+/// parameters are matched in any order. Otherwise, e.g. if the query may be
+/// expanded from a reserved expression (e.g. `{+path}`), the whole topic is
+/// matched in order.
+///
+/// [URI Template]: https://datatracker.ietf.org/doc/html/rfc6570
+pub(super) fn compile(uri_template: &str) -> Matcher {
+    let tokens = tokenize(uri_template);
+    compile_query(&tokens).unwrap_or_else(|| Matcher::Regex(compile_regex(&tokens)))
+}
+
+impl Matcher {
+    /// Returns `true` if the given (serialized) topic is matched.
+    ///
+    /// Percent-encoding is normalized first, see
+    /// [`normalize_percent_encoding`].
+    pub(super) fn is_match(&self, topic: &str) -> bool {
+        let topic = normalize_percent_encoding(topic);
+        match self {
+            Self::Regex(regex) => regex.is_match(&topic),
+            Self::Query {
+                path,
+                params,
+                varspecs,
+            } => {
+                // The query of the URI Template cannot be expanded to a fragment.
+                if topic.contains('#') {
+                    return false;
+                }
+                let (topic_path, query) = topic.split_once('?').unwrap_or((&topic, ""));
+                if !path.is_match(topic_path) {
+                    return false;
+                }
+
+                let mut query_params: Vec<_> = query
+                    .split('&')
+                    .filter(|query_param| !query_param.is_empty())
+                    .map(Some)
+                    .collect();
+                for param in params {
+                    let Some(query_param) = query_params
+                        .iter_mut()
+                        .find(|query_param| query_param.is_some_and(|qp| param.is_match(qp)))
+                    else {
+                        return false;
+                    };
+                    *query_param = None;
+                }
+
+                // Query parameters matched by an exploded varspec can always
+                // be expanded from it, so only the others need to be matched
+                // by distinct varspecs.
+                let mut candidates = Vec::new();
+                for query_param in query_params.into_iter().flatten() {
+                    let query_param = format!("&{query_param}");
+                    let matched: Vec<_> = varspecs
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, varspec)| varspec.regex.is_match(&query_param))
+                        .collect();
+                    if matched.iter().any(|(_, varspec)| varspec.explode) {
+                        continue;
+                    }
+                    if matched.is_empty() {
+                        return false;
+                    }
+                    candidates.push(matched.into_iter().map(|(i, _)| i).collect::<Vec<_>>());
+                }
+                let mut assigned = vec![None; varspecs.len()];
+                (0..candidates.len()).all(|query_param| {
+                    assign_varspec(
+                        query_param,
+                        &candidates,
+                        &mut assigned,
+                        &mut vec![false; varspecs.len()],
+                    )
+                })
+            },
+        }
+    }
+}
+
+/// Assigns a distinct varspec among its candidates to the given query
+/// parameter, reassigning the varspecs of other query parameters if needed,
+/// i.e. finds an augmenting path for bipartite matching.
+///
+/// Returns `false` if there is no such assignment.
+fn assign_varspec(
+    query_param: usize,
+    candidates: &[Vec<usize>],
+    assigned: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for &varspec in &candidates[query_param] {
+        if visited[varspec] {
+            continue;
+        }
+        visited[varspec] = true;
+        if assigned[varspec]
+            .is_none_or(|other| assign_varspec(other, candidates, assigned, visited))
+        {
+            assigned[varspec] = Some(query_param);
+            return true;
+        }
+    }
+
+    false
+}
+
+fn tokenize(uri_template: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = uri_template;
+    while !rest.is_empty() {
+        match rest.find('{') {
+            Some(0) => {
+                let (expression, tail) = rest[1..]
+                    .split_once('}')
+                    .expect("`uri_template` should have been validated");
+                tokens.push(Token::Expression(expression));
+                rest = tail;
+            },
+            Some(start) => {
+                tokens.push(Token::Literal(&rest[..start]));
+                rest = &rest[start..];
+            },
+            None => {
+                tokens.push(Token::Literal(rest));
+                rest = "";
+            },
+        }
+    }
+
+    tokens
+}
+
+fn compile_regex(tokens: &[Token<'_>]) -> Regex {
+    let mut pattern = String::from("^");
+    for token in tokens {
+        match token {
+            Token::Literal(literal) => write_literal(&mut pattern, literal),
+            Token::Expression(expression) => write_expression(&mut pattern, expression),
+        }
+    }
+    pattern.push('$');
+
+    Regex::new(&pattern).expect("`pattern` should be a valid regex")
+}
+
+/// Compiles a [`Matcher::Query`], or returns `None` if the URI Template has
+/// no query, or its query cannot be matched in any order.
+fn compile_query(tokens: &[Token<'_>]) -> Option<Matcher> {
+    let mut path = Vec::new();
+    let mut query = Vec::new();
+    let mut tokens = tokens.iter().copied();
+    for token in tokens.by_ref() {
+        match token {
+            Token::Literal(literal) => match literal.find(['?', '#']) {
+                Some(start) if literal[start..].starts_with('?') => {
+                    path.push(Token::Literal(&literal[..start]));
+                    query.push(Token::Literal(&literal[start.saturating_add(1)..]));
+                    break;
+                },
+                Some(_) => return None,
+                None => path.push(Token::Literal(literal)),
+            },
+            Token::Expression(expression) => match expression.chars().next() {
+                Some('+' | '#') => return None,
+                Some('?') => {
+                    query.push(Token::Expression(expression));
+                    break;
+                },
+                _ => path.push(Token::Expression(expression)),
+            },
+        }
+    }
+    if query.is_empty() {
+        return None;
+    }
+    query.extend(tokens);
+
+    let mut params = Vec::new();
+    let mut varspecs = Vec::new();
+    let mut param = Vec::new();
+    for token in query {
+        match token {
+            Token::Literal(literal) => {
+                if literal.contains('#') {
+                    return None;
+                }
+                let mut pieces = literal.split('&');
+                if let Some(piece) = pieces.next() {
+                    param.push(Token::Literal(piece));
+                }
+                for piece in pieces {
+                    params.push(std::mem::take(&mut param));
+                    param.push(Token::Literal(piece));
+                }
+            },
+            Token::Expression(expression) => match expression.chars().next() {
+                Some(operator @ ('?' | '&')) => {
+                    if param
+                        .iter()
+                        .any(|token| !matches!(token, Token::Literal("")))
+                    {
+                        // `{?category}` in the middle of a query parameter
+                        // would expand to another "?".
+                        if operator == '?' {
+                            return None;
+                        }
+                        params.push(std::mem::take(&mut param));
+                    }
+                    param.clear();
+                    varspecs.extend(expression[1..].split(',').map(|varspec| QueryVarspec {
+                        regex: compile_regex(&[Token::Expression(&format!("&{varspec}"))]),
+                        explode: varspec.ends_with('*'),
+                    }));
+                },
+                Some('+' | '#' | '.' | '/' | ';') => return None,
+                _ => param.push(Token::Expression(expression)),
+            },
+        }
+    }
+    params.push(param);
+
+    Some(Matcher::Query {
+        path: compile_regex(&path),
+        params: params
+            .iter()
+            .filter(|param| {
+                param
+                    .iter()
+                    .any(|token| !matches!(token, Token::Literal("")))
+            })
+            .map(|param| compile_regex(param))
+            .collect(),
+        varspecs,
+    })
+}
+
+/// Normalizes the percent-encoding of the given URI: percent-encoded
+/// unreserved characters are decoded, and the hexadecimal digits of other
+/// percent-encoded octets are uppercased.
+///
+/// [RFC 3986, Section 6.2.2](https://datatracker.ietf.org/doc/html/rfc3986#section-6.2.2)
+fn normalize_percent_encoding(uri: &str) -> Cow<'_, str> {
+    if !uri.contains('%') {
+        return Cow::Borrowed(uri);
+    }
+
+    let mut normalized = String::with_capacity(uri.len());
+    let mut rest = uri;
+    while let Some(start) = rest.find('%') {
+        normalized.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let octet = rest
+            .get(1..3)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match octet {
+            Some(octet) if octet.is_ascii_alphanumeric() || b"-._~".contains(&octet) => {
+                normalized.push(char::from(octet));
+                rest = &rest[3..];
+            },
+            Some(octet) => {
+                write!(normalized, "%{octet:02X}").unwrap();
+                rest = &rest[3..];
+            },
+            None => {
+                normalized.push('%');
+                rest = &rest[1..];
+            },
+        }
+    }
+    normalized.push_str(rest);
+
+    Cow::Owned(normalized)
+}
+
+fn write_literal(pattern: &mut String, literal: &str) {
+    // `Url` serializes non-ASCII characters as percent-encoded UTF-8.
+    let mut encoded = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if c.is_ascii() {
+            encoded.push(c);
+        } else {
+            for b in c.encode_utf8(&mut [0; 4]).bytes() {
+                write!(encoded, "%{b:02X}").unwrap();
+            }
+        }
+    }
+    pattern.push_str(&regex::escape(&normalize_percent_encoding(&encoded)));
+}
+
+fn write_expression(pattern: &mut String, expression: &str) {
+    let (operator, variable_list) = match expression.chars().next() {
+        Some(c @ ('+' | '#' | '.' | '/' | ';' | '?' | '&')) => (c, &expression[1..]),
+        _ => ('\0', expression),
+    };
+    let operator = match operator {
+        '+' => Operator {
+            first: "",
+            sep: ",",
+            named: false,
+            allow_reserved: true,
+        },
+        '#' => Operator {
+            first: "#",
+            sep: ",",
+            named: false,
+            allow_reserved: true,
+        },
+        '.' => Operator {
+            first: ".",
+            sep: ".",
+            named: false,
+            allow_reserved: false,
+        },
+        '/' => Operator {
+            first: "/",
+            sep: "/",
+            named: false,
+            allow_reserved: false,
+        },
+        ';' => Operator {
+            first: ";",
+            sep: ";",
+            named: true,
+            allow_reserved: false,
+        },
+        '?' => Operator {
+            first: "?",
+            sep: "&",
+            named: true,
+            allow_reserved: false,
+        },
+        '&' => Operator {
+            first: "&",
+            sep: "&",
+            named: true,
+            allow_reserved: false,
+        },
+        _ => Operator {
+            first: "",
+            sep: ",",
+            named: false,
+            allow_reserved: false,
+        },
+    };
+
+    let chars = if operator.allow_reserved {
+        format!("(?:[{UNRESERVED}{RESERVED}]|%[0-9A-Fa-f]{{2}})*")
+    } else {
+        format!("(?:[{UNRESERVED}]|%[0-9A-Fa-f]{{2}})*")
+    };
+    // A list value (without the explode modifier) is joined with ",".
+    let value = format!("{chars}(?:,{chars})*");
+
+    let item = if operator.named {
+        // An exploded associative array value uses its own keys as names.
+        let names = if variable_list
+            .split(',')
+            .any(|varspec| varspec.ends_with('*'))
+        {
+            chars.clone()
+        } else {
+            variable_list
+                .split(',')
+                .map(|varspec| regex::escape(varspec.split(':').next().unwrap()))
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+        format!("(?:{names})(?:={value})?")
+    } else {
+        value
+    };
+
+    write!(
+        pattern,
+        "(?:{first}{item}(?:{sep}{item})*)?",
+        first = regex::escape(operator.first),
+        sep = regex::escape(operator.sep),
+    )
+    .unwrap();
+}
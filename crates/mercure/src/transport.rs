@@ -0,0 +1,355 @@
+//! The HTTP transport used by a [`GenericClient`] to communicate with the
+//! Mercure hub.
+//!
+//! [`ReqwestTransport`], backed by [`reqwest::Client`], is used by default.
+//! Implement [`Transport`] to use another HTTP library, or to add middleware.
+//!
+//! [`GenericClient`]: crate::client::GenericClient
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::StatusCode;
+use url::Url;
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod wasm;
+
+/// An HTTP transport for communicating with the Mercure hub.
+pub trait Transport: Clone + fmt::Debug + Send + Sync + 'static {
+    /// The error type returned when sending a request or reading a response
+    /// body fails.
+    type Error: Error + Send + Sync + 'static;
+
+    /// The stream of chunks of a response body.
+    type Body: Stream<Item = Result<Bytes, Self::Error>> + Send + 'static;
+
+    /// Sends a `POST` request, returning the response once its status has been
+    /// received.
+    fn post(
+        &self,
+        request: TransportRequest,
+    ) -> impl Future<Output = Result<TransportResponse<Self::Body>, Self::Error>> + Send;
+
+    /// Sends a `GET` request, returning the response once its status has been
+    /// received.
+    ///
+    /// The response body may be a long-lived event stream.
+    fn get(
+        &self,
+        request: TransportRequest,
+    ) -> impl Future<Output = Result<TransportResponse<Self::Body>, Self::Error>> + Send;
+
+    /// Returns `true` if the error means that the connection to the Mercure
+    /// hub could not be established, so the request was not sent.
+    ///
+    /// This decides whether a publish request may be retried. Returns `false`
+    /// by default.
+    ///
+    /// See [`RetryPolicy`].
+    ///
+    /// [`RetryPolicy`]: crate::client::RetryPolicy
+    fn is_connect_error(err: &Self::Error) -> bool {
+        let _ = err;
+        false
+    }
+}
+
+/// A request to the Mercure hub.
+#[derive(Clone, Debug)]
+pub struct TransportRequest {
+    url: Url,
+    headers: HeaderMap,
+    body: Bytes,
+    timeout: Option<Duration>,
+    accept_compression: bool,
+}
+
+/// A response from the Mercure hub.
+#[derive(Debug)]
+pub struct TransportResponse<B> {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: B,
+}
+
+/// A [`Transport`] backed by [`reqwest::Client`].
+///
+/// On `wasm32` targets, `reqwest` is backed by the browser's `fetch` API, and
+/// the `wasm` feature must be enabled. Connection errors cannot be told apart
+/// from other network errors there, so publish requests are only retried after
+/// a server error status.
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport(reqwest::Client);
+
+impl TransportRequest {
+    pub(crate) fn new(
+        url: Url,
+        headers: HeaderMap,
+        body: Bytes,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            url,
+            headers,
+            body,
+            timeout,
+            accept_compression: true,
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    pub(crate) fn with_accept_compression(mut self, accept_compression: bool) -> Self {
+        self.accept_compression = accept_compression;
+        self
+    }
+
+    pub(crate) fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Returns the URL of the request.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the headers of the request.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the body of the request, which is empty for `GET` requests.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Returns the timeout for the request, from when it starts connecting
+    /// until the response body has finished, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns whether the transport may ask the Mercure hub to compress the
+    /// response body, e.g. with `Accept-Encoding: gzip`.
+    ///
+    /// A transport must only do so if it decompresses the response body
+    /// before returning it, as it is parsed as is. If `false`, e.g. because
+    /// compression was disabled for a subscription, a transport whose HTTP
+    /// library negotiates compression by itself should ask for an uncompressed
+    /// response body instead, e.g. with `Accept-Encoding: identity`.
+    pub fn accept_compression(&self) -> bool {
+        self.accept_compression
+    }
+}
+
+impl<B> TransportResponse<B> {
+    /// Constructs a new `TransportResponse`, without headers.
+    pub fn new(status: StatusCode, body: B) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body,
+        }
+    }
+
+    /// Sets the headers of the response.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Returns the status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the body of the response.
+    pub fn into_body(self) -> B {
+        self.body
+    }
+}
+
+impl ReqwestTransport {
+    /// Constructs a new `ReqwestTransport` with the given HTTP client.
+    pub fn new(http_client: reqwest::Client) -> Self {
+        Self(http_client)
+    }
+
+    /// Returns the HTTP client.
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.0
+    }
+
+    async fn send(
+        request_builder: reqwest::RequestBuilder,
+        request: TransportRequest,
+    ) -> reqwest::Result<TransportResponse<<Self as Transport>::Body>> {
+        let mut headers = request.headers;
+        if !request.accept_compression {
+            // `reqwest` negotiates the content codings it can decompress, if
+            // enabled for the HTTP client.
+            headers
+                .entry(header::ACCEPT_ENCODING)
+                .or_insert(HeaderValue::from_static("identity"));
+        }
+        let mut request_builder = request_builder.headers(headers);
+        if let Some(timeout) = request.timeout {
+            request_builder = request_builder.timeout(timeout);
+        }
+        #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+        return wasm::send(request_builder).await;
+        #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+        {
+            let res = request_builder.send().await?;
+            let status = res.status();
+            let headers = res.headers().clone();
+            let body: <Self as Transport>::Body = Box::pin(res.bytes_stream());
+
+            Ok(TransportResponse::new(status, body).with_headers(headers))
+        }
+    }
+}
+
+impl From<reqwest::Client> for ReqwestTransport {
+    fn from(http_client: reqwest::Client) -> Self {
+        Self(http_client)
+    }
+}
+
+impl Transport for ReqwestTransport {
+    type Body = Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>;
+    type Error = reqwest::Error;
+
+    async fn post(
+        &self,
+        request: TransportRequest,
+    ) -> Result<TransportResponse<Self::Body>, Self::Error> {
+        let request_builder = self.0.post(request.url.clone()).body(request.body.clone());
+        Self::send(request_builder, request).await
+    }
+
+    async fn get(
+        &self,
+        request: TransportRequest,
+    ) -> Result<TransportResponse<Self::Body>, Self::Error> {
+        let request_builder = self.0.get(request.url.clone());
+        Self::send(request_builder, request).await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_connect_error(err: &Self::Error) -> bool {
+        err.is_connect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::{Context as _, Result};
+    use futures_util::stream::{self, Iter};
+    use futures_util::TryStreamExt as _;
+
+    use super::*;
+    use crate::client::tests::{test_client_builder, UNUSED_HUB_ADDR};
+    use crate::client::PublishUpdatePrivacy;
+    use crate::{Topic, TopicSelector};
+
+    #[derive(Clone, Debug, Default)]
+    struct StaticTransport {
+        requests: Arc<Mutex<Vec<TransportRequest>>>,
+    }
+
+    impl StaticTransport {
+        fn respond(
+            &self,
+            request: TransportRequest,
+            body: &'static str,
+        ) -> TransportResponse<<Self as Transport>::Body> {
+            self.requests.lock().unwrap().push(request);
+            TransportResponse::new(
+                StatusCode::OK,
+                stream::iter(vec![Ok(Bytes::from_static(body.as_bytes()))]),
+            )
+        }
+    }
+
+    impl Transport for StaticTransport {
+        type Body = Iter<std::vec::IntoIter<Result<Bytes, Infallible>>>;
+        type Error = Infallible;
+
+        async fn post(
+            &self,
+            request: TransportRequest,
+        ) -> Result<TransportResponse<Self::Body>, Self::Error> {
+            Ok(self.respond(request, "urn:uuid:1"))
+        }
+
+        async fn get(
+            &self,
+            request: TransportRequest,
+        ) -> Result<TransportResponse<Self::Body>, Self::Error> {
+            Ok(self.respond(request, "id: urn:uuid:1\ndata: 1\n\n"))
+        }
+    }
+
+    #[tokio::test]
+    async fn it_uses_custom_transport() -> Result<()> {
+        let transport = StaticTransport::default();
+        let client = test_client_builder(UNUSED_HUB_ADDR)?
+            .transport(transport.clone())
+            .build();
+
+        let revision_id = client
+            .publish_update(
+                Topic::new("https://example.com/books/1".parse()?, vec![]),
+                Some("1"),
+                PublishUpdatePrivacy::Public,
+            )
+            .await?;
+        assert_eq!(revision_id.to_string(), "urn:uuid:1");
+
+        let updates: Vec<_> = client
+            .subscribe(vec![TopicSelector::Wildcard], None)
+            .await?
+            .auto_reconnect(false)
+            .try_collect()
+            .await?;
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].data(), "1");
+
+        let requests = transport.requests.lock().unwrap();
+        let publish_request = requests.first().context("publish request should be sent")?;
+        assert_eq!(
+            publish_request.body().as_ref(),
+            b"topic=https%3A%2F%2Fexample.com%2Fbooks%2F1&data=1"
+        );
+        assert!(publish_request
+            .headers()
+            .contains_key(reqwest::header::AUTHORIZATION));
+        let subscribe_request = requests
+            .get(1)
+            .context("subscribe request should be sent")?;
+        assert_eq!(
+            subscribe_request.url().as_str(),
+            "http://127.0.0.1:0/.well-known/mercure?topic=*"
+        );
+        assert!(subscribe_request.accept_compression());
+        assert!(!subscribe_request
+            .headers()
+            .contains_key(reqwest::header::ACCEPT_ENCODING));
+        Ok(())
+    }
+}
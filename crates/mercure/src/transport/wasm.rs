@@ -0,0 +1,68 @@
+//! Support for [`ReqwestTransport`] on `wasm32` targets, where `reqwest` is
+//! backed by the browser's `fetch` API.
+//!
+//! The futures and streams of `reqwest` hold JavaScript values there, so they
+//! are `!Send`. They are driven on the local task instead, passing the
+//! response and the chunks of its body over channels, so that the futures
+//! returned by the transport and its response bodies are `Send`.
+
+use std::pin::pin;
+
+use futures_util::future::{self, Either};
+use futures_util::{stream, StreamExt as _};
+use tokio::sync::{mpsc, oneshot};
+
+use super::{ReqwestTransport, Transport, TransportResponse};
+
+type Body = <ReqwestTransport as Transport>::Body;
+
+pub(super) async fn send(
+    request_builder: reqwest::RequestBuilder,
+) -> reqwest::Result<TransportResponse<Body>> {
+    let (mut response, response_receiver) = oneshot::channel();
+    wasm_bindgen_futures::spawn_local(async move {
+        let res = match future::select(pin!(request_builder.send()), pin!(response.closed())).await
+        {
+            Either::Left((res, _)) => res,
+            // The request has been cancelled, so abort it.
+            Either::Right(((), _)) => return,
+        };
+        let res = match res {
+            Ok(res) => res,
+            Err(err) => {
+                let _ = response.send(Err(err));
+                return;
+            },
+        };
+
+        let status = res.status();
+        let headers = res.headers().clone();
+        let (chunks, mut chunks_receiver) = mpsc::channel(1);
+        let body: Body = Box::pin(stream::poll_fn(move |cx| chunks_receiver.poll_recv(cx)));
+        if response
+            .send(Ok(
+                TransportResponse::new(status, body).with_headers(headers)
+            ))
+            .is_err()
+        {
+            return;
+        }
+
+        let mut bytes_stream = pin!(res.bytes_stream());
+        loop {
+            let chunk = match future::select(bytes_stream.next(), pin!(chunks.closed())).await {
+                Either::Left((Some(chunk), _)) => chunk,
+                // Dropping the response body aborts the request, e.g. when an
+                // event stream is closed.
+                Either::Left((None, _)) | Either::Right(((), _)) => break,
+            };
+            if chunks.send(chunk).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    response_receiver
+        .await
+        .expect("the local task should send the response unless the request is cancelled")
+}
@@ -0,0 +1,1577 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use futures_util::future::BoxFuture;
+use futures_util::task::AtomicWaker;
+use futures_util::StreamExt as _;
+use reqwest::header::HeaderValue;
+use ring::rand::{SecureRandom as _, SystemRandom};
+#[cfg(feature = "serde-errors")]
+use serde::ser::SerializeStruct as _;
+use serde::Deserialize;
+#[cfg(feature = "serde-errors")]
+use serde::Serialize;
+
+use self::event_stream::{Event, EventStreamParser};
+use crate::client::CancelSignal;
+use crate::time;
+use crate::transport::{ReqwestTransport, Transport, TransportRequest, TransportResponse};
+
+mod event_stream;
+
+/// An update received from the Mercure hub.
+///
+/// [The Mercure Protocol, Section 4](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-4)
+///
+/// # Note
+///
+/// The topic of the update is not sent by the Mercure hub, so it is not
+/// available here. Include it in the data if subscribers need it.
+///
+/// The topic selectors of the subscription cannot stand in for it: an update
+/// matched by a URI Template or by the wildcard has a topic which is not among
+/// them, and with several topic selectors there is no telling which one
+/// matched. Subscribing to a single topic is the only case where the topic is
+/// known, and then the subscriber already has it.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Update {
+    id: Option<String>,
+    event_type: String,
+    data: String,
+}
+
+/// A subscription event, published by the Mercure hub when a subscriber
+/// subscribes or unsubscribes.
+///
+/// The Mercure hub publishes subscription events to topics of the form
+/// `/.well-known/mercure/subscriptions/{topic}/{subscriber}`, if enabled.
+/// Subscribe to them with a matching [`TopicSelector`], and parse the received
+/// updates with [`Update::subscription_event`].
+///
+/// [The Mercure Protocol, Section 6](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-6)
+///
+/// [`TopicSelector`]: crate::TopicSelector
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+pub struct SubscriptionEvent {
+    #[serde(rename = "@context", default)]
+    context: Option<String>,
+    id: String,
+    r#type: String,
+    topic: String,
+    subscriber: String,
+    active: bool,
+    #[serde(default)]
+    payload: Option<serde_json::Value>,
+}
+
+/// A stream of updates received from the Mercure hub.
+///
+/// Returned by [`Client::subscribe`].
+///
+/// # Reconnection
+///
+/// When the connection is closed or fails, the stream reconnects to the
+/// Mercure hub, sending the ID of the last received update in the
+/// `Last-Event-ID` header so that no updates are missed.
+///
+/// The reconnection delay is set by the "retry" field sent by the Mercure hub,
+/// or the initial delay of the [`ReconnectPolicy`], and is doubled on each
+/// consecutive failure to reconnect. If the Mercure hub responds to a
+/// reconnection with an error status, the error is returned and the stream
+/// ends.
+///
+/// See [`SubscriptionBuilder::reconnect_policy`] to configure the delay, and
+/// [`UpdateStream::auto_reconnect`] to disable reconnection.
+///
+/// # Pausing
+///
+/// The stream can be paused with [`UpdateStream::pause`] without closing the
+/// connection, e.g. while the application is in the background. See
+/// [`UpdateStream::pause_handle`] to pause it from another task.
+///
+/// # Cancellation
+///
+/// Dropping the stream closes the connection. To end the stream on a signal
+/// instead, use [`SubscriptionBuilder::cancel_on`]: the stream then closes the
+/// connection and ends, discarding any buffered updates.
+///
+/// [The Mercure Protocol, Section 7](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-7)
+///
+/// [`Client::subscribe`]: crate::Client::subscribe
+/// [`SubscriptionBuilder::cancel_on`]: crate::client::SubscriptionBuilder::cancel_on
+/// [`SubscriptionBuilder::reconnect_policy`]: crate::client::SubscriptionBuilder::reconnect_policy
+pub struct UpdateStream<T = ReqwestTransport>
+where
+    T: Transport,
+{
+    transport: T,
+    /// The subscribe request, without the `Last-Event-ID` header.
+    request: TransportRequest,
+    state: UpdateStreamState<T>,
+    parser: EventStreamParser,
+    last_event_id: Option<String>,
+    on_event_id: Option<OnEventId>,
+    /// The replay of the history of the Mercure hub, if resuming and not yet
+    /// live.
+    replay: Option<Replay>,
+    take_until: Option<TakeUntil>,
+    auto_reconnect: bool,
+    pause_handle: PauseHandle,
+    reconnect_policy: ReconnectPolicy,
+    failed_reconnects: u32,
+    cancel_signal: Option<CancelSignal<'static>>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+type OnEventId = Box<dyn FnMut(&str) + Send>;
+
+type TakeUntil = Box<dyn FnMut(&Update) -> bool + Send>;
+
+/// A handle for pausing and resuming an [`UpdateStream`], e.g. from another
+/// task than the one polling it.
+///
+/// Returned by [`UpdateStream::pause_handle`].
+#[derive(Clone, Debug, Default)]
+pub struct PauseHandle(Arc<PauseState>);
+
+#[derive(Debug, Default)]
+struct PauseState {
+    paused: AtomicBool,
+    waker: AtomicWaker,
+}
+
+#[derive(Debug)]
+struct Replay {
+    /// The ID of the last update in the history of the Mercure hub when
+    /// subscribing, if it was sent.
+    live_event_id: Option<String>,
+    /// The number of replayed updates left before the stream ends, if limited.
+    remaining: Option<usize>,
+}
+
+/// The policy for delaying reconnection to the Mercure hub.
+///
+/// The delay starts at the "retry" field sent by the Mercure hub, or the
+/// initial delay if none was sent, and is doubled on each consecutive failure
+/// to reconnect, up to the maximum delay. A "retry" field shorter than
+/// [`ReconnectPolicy::MIN_DELAY`] is raised to it, so that a Mercure hub which
+/// immediately closes the connection is not reconnected to in a hot loop.
+///
+/// With jitter, which is enabled by default, each delay is randomized between
+/// half of it and all of it, so that many subscribers do not reconnect at the
+/// same time.
+///
+/// The default policy has an initial delay of 3 seconds and a maximum delay of
+/// 96 seconds, with jitter.
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// # use std::time::Duration;
+/// #
+/// use mercure::update::ReconnectPolicy;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let reconnect_policy = ReconnectPolicy::new(Duration::from_secs(1), Duration::from_secs(60))?;
+/// assert!(ReconnectPolicy::new(Duration::ZERO, Duration::from_secs(60)).is_err());
+/// assert!(ReconnectPolicy::new(Duration::from_secs(1), Duration::from_millis(500)).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ReconnectPolicy {
+    initial_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+}
+
+/// An error returned from [`ReconnectPolicy::new`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ReconnectPolicyError {
+    kind: ReconnectPolicyErrorKind,
+    inner: Box<dyn Error + Send + Sync + 'static>,
+}
+
+/// The various types of errors that can cause [`ReconnectPolicy::new`] to
+/// fail.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
+#[non_exhaustive]
+pub enum ReconnectPolicyErrorKind {
+    /// The initial delay is shorter than [`ReconnectPolicy::MIN_DELAY`].
+    InitialDelayTooShort,
+    /// The maximum delay is shorter than the initial delay.
+    MaxDelayTooShort,
+}
+
+type ReconnectFuture<T> = Pin<
+    Box<
+        dyn Future<
+                Output = Result<TransportResponse<<T as Transport>::Body>, <T as Transport>::Error>,
+            > + Send,
+    >,
+>;
+
+enum UpdateStreamState<T>
+where
+    T: Transport,
+{
+    Streaming(Pin<Box<T::Body>>),
+    Reconnecting(ReconnectFuture<T>),
+    Done,
+}
+
+/// An error returned from [`UpdateStream`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct StreamError {
+    kind: StreamErrorKind,
+    inner: Box<dyn Error + Send + Sync + 'static>,
+}
+
+/// The various types of errors that can be returned from [`UpdateStream`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
+#[non_exhaustive]
+pub enum StreamErrorKind {
+    /// Failed to read event stream from Mercure hub.
+    ReadResponse,
+    /// Mercure hub responded to reconnection with an error status.
+    ErrorStatus,
+}
+
+/// A stream of updates received from the Mercure hub, with their data decoded.
+///
+/// Returned by [`UpdateStream::decode_with`].
+pub struct DecodeWith<T, F>
+where
+    T: Transport,
+{
+    update_stream: UpdateStream<T>,
+    decode: F,
+}
+
+/// Dispatches updates to handlers by their type, i.e. the SSE `event` field.
+///
+/// Updates published without a type have the type `message`, and are
+/// dispatched to the handler registered with [`UpdateRouter::message`]. Updates
+/// of a type without a handler are dispatched to the fallback handler, if any,
+/// or dropped otherwise.
+///
+/// Each handler is awaited before the next update is dispatched, so updates are
+/// handled in order.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// #
+/// use mercure::update::UpdateRouter;
+/// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+/// # use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// # let http_client = reqwest::Client::new();
+/// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+/// # let publisher_jwt = PublisherJwt::new(
+/// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+/// #     vec![TopicSelector::Wildcard],
+/// # )?;
+/// #
+/// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+/// #
+/// let update_stream = client
+///     .subscribe(vec![TopicSelector::Wildcard], None)
+///     .await?;
+/// UpdateRouter::new()
+///     .message(|update| async move {
+///         println!("updated: {data}", data = update.data());
+///     })
+///     .route("BookDeleted", |update| async move {
+///         println!("deleted: {data}", data = update.data());
+///     })
+///     .fallback(|update| async move {
+///         eprintln!("unexpected update type: {event_type}", event_type = update.event_type());
+///     })
+///     .run(update_stream)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "routers do nothing unless `run` is called"]
+pub struct UpdateRouter {
+    routes: HashMap<String, UpdateHandler>,
+    fallback: Option<UpdateHandler>,
+}
+
+type UpdateHandler = Box<dyn FnMut(Update) -> BoxFuture<'static, ()> + Send>;
+
+/// An error returned from [`DecodeWith`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DecodeUpdateError {
+    kind: DecodeUpdateErrorKind,
+    inner: Box<dyn Error + Send + Sync + 'static>,
+}
+
+/// The various types of errors that can be returned from [`DecodeWith`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde-errors", derive(Serialize))]
+#[non_exhaustive]
+pub enum DecodeUpdateErrorKind {
+    /// The [`UpdateStream`] returned an error. The [`StreamError`] is the
+    /// source of this error.
+    Stream,
+    /// Failed to decode the data of an update. The error returned by the
+    /// decoder is the source of this error.
+    Decode,
+}
+
+/// An error which can be returned when parsing a [`SubscriptionEvent`] from an
+/// [`Update`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ParseSubscriptionEventError {
+    inner: serde_json::Error,
+}
+
+impl Update {
+    /// Returns the ID of the update, i.e. the SSE `id` field, if any.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the type of the update, i.e. the SSE `event` field.
+    ///
+    /// This is `message` if the update was published without a type.
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+
+    /// Returns the data of the update, i.e. the SSE `data` fields joined with
+    /// newlines.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// Parses the data of the update as a [`SubscriptionEvent`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not a valid subscription event.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::StreamExt as _;
+    /// use mercure::TopicSelector;
+    /// # use mercure::{HubUrl, PublisherJwt};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let topic_selectors = vec![TopicSelector::UriTemplate(
+    ///     "/.well-known/mercure/subscriptions/{topic}/{subscriber}".try_into()?,
+    /// )];
+    ///
+    /// let mut update_stream = client.subscribe(topic_selectors, None).await?;
+    /// while let Some(update) = update_stream.next().await {
+    ///     let subscription_event = update?.subscription_event()?;
+    ///     println!(
+    ///         "{subscriber} is {status} to {topic}",
+    ///         subscriber = subscription_event.subscriber(),
+    ///         status = if subscription_event.is_active() {
+    ///             "subscribed"
+    ///         } else {
+    ///             "unsubscribed"
+    ///         },
+    ///         topic = subscription_event.topic(),
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subscription_event(&self) -> Result<SubscriptionEvent, ParseSubscriptionEventError> {
+        serde_json::from_str(&self.data).map_err(|err| ParseSubscriptionEventError { inner: err })
+    }
+}
+
+impl SubscriptionEvent {
+    /// Returns the JSON-LD context of the subscription event, if any.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// Returns the ID of the subscription, i.e. the topic of the subscription
+    /// event.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the type of the subscription event, which is `Subscription`.
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    /// Returns the topic selector of the subscription.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Returns the identifier of the subscriber.
+    pub fn subscriber(&self) -> &str {
+        &self.subscriber
+    }
+
+    /// Returns `true` if the subscriber is subscribed, or `false` if they have
+    /// unsubscribed.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Returns the `mercure.payload` claim of the subscriber's JWT, if any.
+    pub fn payload(&self) -> Option<&serde_json::Value> {
+        self.payload.as_ref()
+    }
+}
+
+impl From<Event> for Update {
+    fn from(event: Event) -> Self {
+        Self {
+            id: Some(event.id).filter(|id| !id.is_empty()),
+            event_type: event.event_type,
+            data: event.data,
+        }
+    }
+}
+
+impl<T> UpdateStream<T>
+where
+    T: Transport,
+{
+    pub(crate) fn new(
+        transport: T,
+        request: TransportRequest,
+        last_event_id: Option<String>,
+        response: TransportResponse<T::Body>,
+    ) -> Self {
+        // The Mercure hub sends the ID of the last update in its history, so
+        // that a subscriber resuming from an older ID knows when it is live.
+        let replay = last_event_id.as_ref().and_then(|last_event_id| {
+            let live_event_id = response
+                .headers()
+                .get("last-event-id")
+                .and_then(|live_event_id| live_event_id.to_str().ok())
+                .map(ToOwned::to_owned);
+            (live_event_id.as_ref() != Some(last_event_id)).then_some(Replay {
+                live_event_id,
+                remaining: None,
+            })
+        });
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("mercure.subscribe", url = %request.url());
+        #[cfg(feature = "tracing")]
+        span.in_scope(|| tracing::info!("connected to Mercure hub"));
+
+        Self {
+            transport,
+            request,
+            state: UpdateStreamState::Streaming(Box::pin(response.into_body())),
+            parser: EventStreamParser::default(),
+            last_event_id,
+            on_event_id: None,
+            replay,
+            take_until: None,
+            auto_reconnect: true,
+            pause_handle: PauseHandle::default(),
+            reconnect_policy: ReconnectPolicy::default(),
+            failed_reconnects: 0,
+            cancel_signal: None,
+            #[cfg(feature = "tracing")]
+            span,
+        }
+    }
+
+    /// Controls whether to reconnect to the Mercure hub when the connection is
+    /// closed or fails.
+    ///
+    /// Enabled by default. When disabled, the stream returns the error, if
+    /// any, and ends.
+    pub fn auto_reconnect(mut self, auto_reconnect: bool) -> Self {
+        self.auto_reconnect = auto_reconnect;
+        self
+    }
+
+    /// Ends the stream after the first update matching the given predicate,
+    /// e.g. a job completion event in a request-response pattern.
+    ///
+    /// The matching update is returned, then the stream closes the connection
+    /// and ends.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::TryStreamExt as _;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let updates: Vec<_> = client
+    ///     .subscribe(vec![TopicSelector::Wildcard], None)
+    ///     .await?
+    ///     .take_until(|update| update.event_type() == "JobCompleted")
+    ///     .try_collect()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_until<F>(mut self, predicate: F) -> Self
+    where
+        F: FnMut(&Update) -> bool + Send + 'static,
+    {
+        self.take_until = Some(Box::new(predicate));
+        self
+    }
+
+    /// Pauses the stream without closing the connection.
+    ///
+    /// While paused, polling the stream returns `Poll::Pending`, and the
+    /// stream stops reading from the connection, so that TCP backpressure
+    /// slows down the Mercure hub once the buffers are full. The Mercure hub
+    /// may close the connection if the stream is paused for too long, in which
+    /// case the stream reconnects with the last event ID when resumed, unless
+    /// [`UpdateStream::auto_reconnect`] is disabled.
+    ///
+    /// Cancellation with [`SubscriptionBuilder::cancel_on`] still ends the
+    /// stream while paused.
+    ///
+    /// [`SubscriptionBuilder::cancel_on`]: crate::client::SubscriptionBuilder::cancel_on
+    pub fn pause(&self) {
+        self.pause_handle.pause();
+    }
+
+    /// Resumes the stream after [`UpdateStream::pause`].
+    pub fn resume(&self) {
+        self.pause_handle.resume();
+    }
+
+    /// Returns whether the stream is paused.
+    pub fn is_paused(&self) -> bool {
+        self.pause_handle.is_paused()
+    }
+
+    /// Returns a [`PauseHandle`] for pausing and resuming the stream from
+    /// another task, e.g. while a loop is consuming it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::StreamExt as _;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let mut update_stream = client
+    ///     .subscribe(vec![TopicSelector::Wildcard], None)
+    ///     .await?;
+    /// let pause_handle = update_stream.pause_handle();
+    /// tokio::spawn(async move {
+    ///     while let Some(update) = update_stream.next().await {
+    ///         println!("{data}", data = update?.data());
+    ///     }
+    ///     Ok::<_, mercure::update::StreamError>(())
+    /// });
+    ///
+    /// // When the application is in the background.
+    /// pause_handle.pause();
+    /// // When the application is in the foreground again.
+    /// pause_handle.resume();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pause_handle(&self) -> PauseHandle {
+        self.pause_handle.clone()
+    }
+
+    /// Limits the number of updates replayed from the history of the Mercure
+    /// hub when resuming with a last event ID, e.g. to bound the work on
+    /// startup after a long downtime.
+    ///
+    /// The Mercure hub sends the ID of the last update in its history in the
+    /// `Last-Event-ID` response header. Once that update has been received,
+    /// the stream is live and the limit no longer applies. If `limit` updates
+    /// have been replayed before then, the stream closes the connection and
+    /// ends, and [`UpdateStream::last_event_id`] returns the ID to resume
+    /// from later.
+    ///
+    /// If the Mercure hub does not send the `Last-Event-ID` response header,
+    /// the end of the replay cannot be detected, so the limit applies to all
+    /// updates. It has no effect when not resuming with a last event ID.
+    ///
+    /// [The Mercure Protocol, Section 8](https://datatracker.ietf.org/doc/html/draft-dunglas-mercure#section-8)
+    pub fn replay_limit(mut self, limit: usize) -> Self {
+        if let Some(replay) = &mut self.replay {
+            replay.remaining = Some(limit);
+        }
+        self
+    }
+
+    /// Decodes the data of each update with the given decoder, e.g. for
+    /// MessagePack or protobuf payloads.
+    ///
+    /// Errors from the decoder are returned with
+    /// [`DecodeUpdateErrorKind::Decode`], and errors from this stream with
+    /// [`DecodeUpdateErrorKind::Stream`]. A decoding error does not end the
+    /// stream.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::StreamExt as _;
+    /// use mercure::update::DecodeUpdateErrorKind;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let mut book_ids = client
+    ///     .subscribe(vec![TopicSelector::Wildcard], None)
+    ///     .await?
+    ///     .decode_with(|data| data.parse::<u64>());
+    /// while let Some(book_id) = book_ids.next().await {
+    ///     match book_id {
+    ///         Ok(book_id) => println!("{book_id}"),
+    ///         Err(err) if matches!(err.kind(), DecodeUpdateErrorKind::Decode) => {
+    ///             eprintln!("{err}");
+    ///         },
+    ///         Err(err) => return Err(err.into()),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decode_with<F, U, E>(self, decode: F) -> DecodeWith<T, F>
+    where
+        F: FnMut(&str) -> Result<U, E>,
+        E: Into<Box<dyn Error + Send + Sync + 'static>>,
+    {
+        DecodeWith {
+            update_stream: self,
+            decode,
+        }
+    }
+
+    /// Returns the ID of the last received update, or the last event ID
+    /// passed to [`Client::subscribe`] if no update with an ID has been
+    /// received yet.
+    ///
+    /// This can be persisted to resume the subscription after a restart.
+    ///
+    /// [`Client::subscribe`]: crate::Client::subscribe
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Sets a callback which is called with each new last event ID, i.e. the
+    /// ID of a received update when it differs from the previous one, e.g. to
+    /// persist it as a checkpoint for resuming the subscription after a
+    /// restart.
+    ///
+    /// The callback is called synchronously while polling the stream, before
+    /// the update is returned, so it should not block.
+    ///
+    /// # Delivery
+    ///
+    /// As the callback is called before the update is handed to the consumer,
+    /// checkpointing in it gives at-most-once delivery: if the process stops
+    /// after the checkpoint but before the update is processed, resuming from
+    /// the checkpoint skips that update. For at-least-once delivery, persist
+    /// [`Update::id`] after processing each update instead, as shown below.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::StreamExt as _;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let mut update_stream = client
+    ///     .subscribe(vec![TopicSelector::Wildcard], None)
+    ///     .await?
+    ///     .on_event_id(|last_event_id| {
+    ///         std::fs::write("last-event-id", last_event_id).ok();
+    ///     });
+    /// while let Some(update) = update_stream.next().await {
+    ///     println!("{data}", data = update?.data());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Checkpointing after processing each update, for at-least-once delivery:
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// use futures_util::StreamExt as _;
+    /// # use mercure::{HubUrl, PublisherJwt, TopicSelector};
+    /// # use url::Url;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn Error>> {
+    /// # let http_client = reqwest::Client::new();
+    /// # let hub_url = HubUrl::try_from("https://localhost/.well-known/mercure".parse::<Url>()?)?;
+    /// # let publisher_jwt = PublisherJwt::new(
+    /// #     &b"!ChangeThisMercureHubJWTSecretKey!".to_vec().into(),
+    /// #     vec![TopicSelector::Wildcard],
+    /// # )?;
+    /// #
+    /// # let client = mercure::Client::new(http_client, hub_url, publisher_jwt);
+    /// #
+    /// let mut update_stream = client
+    ///     .subscribe(vec![TopicSelector::Wildcard], None)
+    ///     .await?;
+    /// while let Some(update) = update_stream.next().await {
+    ///     let update = update?;
+    ///     println!("{data}", data = update.data());
+    ///     if let Some(id) = update.id() {
+    ///         std::fs::write("last-event-id", id)?;
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_event_id<F>(mut self, on_event_id: F) -> Self
+    where
+        F: FnMut(&str) + Send + 'static,
+    {
+        self.on_event_id = Some(Box::new(on_event_id));
+        self
+    }
+
+    /// Closes the connection and ends the stream, discarding any buffered
+    /// updates.
+    fn end(&mut self) {
+        self.state = UpdateStreamState::Done;
+        self.parser = EventStreamParser::default();
+    }
+
+    pub(crate) fn with_reconnect_policy(mut self, reconnect_policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = reconnect_policy;
+        self
+    }
+
+    pub(crate) fn with_cancel_signal(
+        mut self,
+        cancel_signal: Option<CancelSignal<'static>>,
+    ) -> Self {
+        self.cancel_signal = cancel_signal;
+        self
+    }
+
+    fn reconnect(&mut self) {
+        let delay = self
+            .reconnect_policy
+            .delay(self.parser.retry(), self.failed_reconnects);
+        let mut request = self.request.clone();
+        if let Some(last_event_id) = self
+            .last_event_id
+            .as_deref()
+            .and_then(|last_event_id| HeaderValue::from_str(last_event_id).ok())
+        {
+            request.headers_mut().insert("last-event-id", last_event_id);
+        }
+        let transport = self.transport.clone();
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            failed_reconnects = self.failed_reconnects,
+            ?delay,
+            "reconnecting to Mercure hub"
+        );
+
+        self.state = UpdateStreamState::Reconnecting(Box::pin(async move {
+            time::sleep(delay).await;
+            transport.get(request).await
+        }));
+    }
+
+    fn end_of_stream(&mut self) {
+        self.parser.discard_pending();
+        if self.auto_reconnect {
+            self.reconnect();
+        } else {
+            self.state = UpdateStreamState::Done;
+        }
+    }
+}
+
+// The response body and reconnection future are boxed, and nothing else is
+// structurally pinned.
+impl<T> Unpin for UpdateStream<T> where T: Transport {}
+
+impl<T> Stream for UpdateStream<T>
+where
+    T: Transport,
+{
+    type Item = Result<Update, StreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        #[cfg(feature = "tracing")]
+        let _entered = this.span.clone().entered();
+        if let Some(cancel_signal) = &mut this.cancel_signal {
+            if Pin::new(cancel_signal).poll(cx).is_ready() {
+                #[cfg(feature = "tracing")]
+                tracing::info!("subscription cancelled");
+                this.cancel_signal = None;
+                this.end();
+                return Poll::Ready(None);
+            }
+        }
+        if this.pause_handle.is_paused() && !matches!(this.state, UpdateStreamState::Done) {
+            this.pause_handle.0.waker.register(cx.waker());
+            // Resumed before the waker was registered.
+            if this.pause_handle.is_paused() {
+                return Poll::Pending;
+            }
+        }
+        if this
+            .replay
+            .as_ref()
+            .is_some_and(|replay| replay.remaining == Some(0))
+        {
+            #[cfg(feature = "tracing")]
+            tracing::info!("replay limit reached");
+            this.replay = None;
+            this.end();
+        }
+        loop {
+            if let Some(event) = this.parser.next_event() {
+                let update = Update::from(event);
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    id = update.id.as_deref(),
+                    r#type = update.event_type,
+                    "received update"
+                );
+                if let Some(id) = &update.id {
+                    if this.last_event_id.as_ref() != Some(id) {
+                        if let Some(on_event_id) = &mut this.on_event_id {
+                            on_event_id(id);
+                        }
+                        this.last_event_id = Some(id.clone());
+                    }
+                }
+                if let Some(replay) = &mut this.replay {
+                    if update.id.is_some() && update.id == replay.live_event_id {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!("replay finished");
+                        this.replay = None;
+                    } else if let Some(remaining) = &mut replay.remaining {
+                        *remaining = remaining.saturating_sub(1);
+                        if *remaining == 0 {
+                            #[cfg(feature = "tracing")]
+                            tracing::info!("replay limit reached");
+                            this.replay = None;
+                            this.end();
+                        }
+                    }
+                }
+                if let Some(take_until) = &mut this.take_until {
+                    if take_until(&update) {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!("received last update");
+                        this.take_until = None;
+                        this.end();
+                    }
+                }
+                return Poll::Ready(Some(Ok(update)));
+            }
+
+            match &mut this.state {
+                UpdateStreamState::Streaming(bytes_stream) => {
+                    match ready!(bytes_stream.as_mut().poll_next(cx)) {
+                        Some(Ok(chunk)) => {
+                            this.parser.feed(&chunk);
+                        },
+                        Some(Err(err)) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(error = %err, "failed to read event stream");
+                            this.end_of_stream();
+                            if !this.auto_reconnect {
+                                return Poll::Ready(Some(Err(StreamError {
+                                    kind: StreamErrorKind::ReadResponse,
+                                    inner: err.into(),
+                                })));
+                            }
+                        },
+                        None => {
+                            #[cfg(feature = "tracing")]
+                            tracing::info!("event stream closed by Mercure hub");
+                            this.end_of_stream();
+                        },
+                    }
+                },
+                UpdateStreamState::Reconnecting(response) => {
+                    match ready!(response.as_mut().poll(cx)) {
+                        Ok(response) if response.status().is_success() => {
+                            #[cfg(feature = "tracing")]
+                            tracing::info!("reconnected to Mercure hub");
+                            this.failed_reconnects = 0;
+                            this.state =
+                                UpdateStreamState::Streaming(Box::pin(response.into_body()));
+                        },
+                        Ok(response) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(
+                                status = response.status().as_u16(),
+                                "Mercure hub responded to reconnection with an error status"
+                            );
+                            this.state = UpdateStreamState::Done;
+                            return Poll::Ready(Some(Err(StreamError {
+                                kind: StreamErrorKind::ErrorStatus,
+                                inner: response.status().to_string().into(),
+                            })));
+                        },
+                        Err(_err) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(error = %_err, "failed to reconnect to Mercure hub");
+                            this.failed_reconnects = this.failed_reconnects.saturating_add(1);
+                            this.reconnect();
+                        },
+                    }
+                },
+                UpdateStreamState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl PauseHandle {
+    /// Pauses the [`UpdateStream`], see [`UpdateStream::pause`].
+    pub fn pause(&self) {
+        self.0.paused.store(true, Ordering::Release);
+    }
+
+    /// Resumes the [`UpdateStream`], see [`UpdateStream::resume`].
+    pub fn resume(&self) {
+        self.0.paused.store(false, Ordering::Release);
+        self.0.waker.wake();
+    }
+
+    /// Returns whether the [`UpdateStream`] is paused.
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::Acquire)
+    }
+}
+
+impl<T, F> DecodeWith<T, F>
+where
+    T: Transport,
+{
+    /// Returns a reference to the underlying [`UpdateStream`].
+    pub fn get_ref(&self) -> &UpdateStream<T> {
+        &self.update_stream
+    }
+
+    /// Returns the underlying [`UpdateStream`].
+    pub fn into_inner(self) -> UpdateStream<T> {
+        self.update_stream
+    }
+}
+
+// Nothing is structurally pinned.
+impl<T, F> Unpin for DecodeWith<T, F> where T: Transport {}
+
+impl<T, F, U, E> Stream for DecodeWith<T, F>
+where
+    T: Transport,
+    F: FnMut(&str) -> Result<U, E>,
+    E: Into<Box<dyn Error + Send + Sync + 'static>>,
+{
+    type Item = Result<U, DecodeUpdateError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let res = match ready!(Pin::new(&mut this.update_stream).poll_next(cx)) {
+            Some(Ok(update)) => (this.decode)(update.data()).map_err(|err| DecodeUpdateError {
+                kind: DecodeUpdateErrorKind::Decode,
+                inner: err.into(),
+            }),
+            Some(Err(err)) => Err(DecodeUpdateError {
+                kind: DecodeUpdateErrorKind::Stream,
+                inner: Box::new(err),
+            }),
+            None => return Poll::Ready(None),
+        };
+        Poll::Ready(Some(res))
+    }
+}
+
+impl<T, F> fmt::Debug for DecodeWith<T, F>
+where
+    T: Transport,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecodeWith")
+            .field("update_stream", &self.update_stream)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for UpdateStream<T>
+where
+    T: Transport,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateStream")
+            .field("transport", &self.transport)
+            .field("request", &self.request)
+            .field("parser", &self.parser)
+            .field("last_event_id", &self.last_event_id)
+            .field("on_event_id", &self.on_event_id.as_ref().map(|_| ".."))
+            .field("replay", &self.replay)
+            .field("take_until", &self.take_until.as_ref().map(|_| ".."))
+            .field("auto_reconnect", &self.auto_reconnect)
+            .field("paused", &self.is_paused())
+            .field("reconnect_policy", &self.reconnect_policy)
+            .field("failed_reconnects", &self.failed_reconnects)
+            .field("cancel_signal", &self.cancel_signal)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UpdateRouter {
+    /// The type of updates published without a type.
+    pub const MESSAGE: &'static str = "message";
+
+    /// Constructs a new `UpdateRouter` without any handlers.
+    pub fn new() -> Self {
+        Self {
+            routes: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Registers the handler for updates of the given type, replacing any
+    /// handler previously registered for it.
+    pub fn route<F, Fut>(mut self, event_type: impl Into<String>, mut handler: F) -> Self
+    where
+        F: FnMut(Update) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.routes.insert(
+            event_type.into(),
+            Box::new(move |update| Box::pin(handler(update))),
+        );
+        self
+    }
+
+    /// Registers the handler for updates published without a type, i.e. of
+    /// type [`UpdateRouter::MESSAGE`].
+    pub fn message<F, Fut>(self, handler: F) -> Self
+    where
+        F: FnMut(Update) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.route(Self::MESSAGE, handler)
+    }
+
+    /// Registers the handler for updates of a type without a handler.
+    pub fn fallback<F, Fut>(mut self, mut handler: F) -> Self
+    where
+        F: FnMut(Update) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.fallback = Some(Box::new(move |update| Box::pin(handler(update))));
+        self
+    }
+
+    /// Dispatches each update of the stream to its handler, until the stream
+    /// ends.
+    ///
+    /// Returns the first error returned from the stream.
+    pub async fn run<S>(mut self, updates: S) -> Result<(), StreamError>
+    where
+        S: Stream<Item = Result<Update, StreamError>>,
+    {
+        let mut updates = pin!(updates);
+        while let Some(update) = updates.next().await {
+            let update = update?;
+            let handler = match self.routes.get_mut(update.event_type()) {
+                Some(handler) => handler,
+                None => match &mut self.fallback {
+                    Some(fallback) => fallback,
+                    None => continue,
+                },
+            };
+            handler(update).await;
+        }
+        Ok(())
+    }
+}
+
+impl Default for UpdateRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for UpdateRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateRouter")
+            .field("routes", &self.routes.keys().collect::<Vec<_>>())
+            .field("fallback", &self.fallback.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl ReconnectPolicy {
+    /// The minimum delay before reconnecting.
+    pub const MIN_DELAY: Duration = Duration::from_millis(100);
+
+    /// Constructs a new `ReconnectPolicy`, with jitter.
+    ///
+    /// Returns an error if the initial delay is shorter than
+    /// [`ReconnectPolicy::MIN_DELAY`], or if the maximum delay is shorter
+    /// than the initial delay.
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Result<Self, ReconnectPolicyError> {
+        if initial_delay < Self::MIN_DELAY {
+            return Err(ReconnectPolicyError {
+                kind: ReconnectPolicyErrorKind::InitialDelayTooShort,
+                inner: format!(
+                    "{initial_delay:?} is shorter than {min_delay:?}",
+                    min_delay = Self::MIN_DELAY
+                )
+                .into(),
+            });
+        }
+        if max_delay < initial_delay {
+            return Err(ReconnectPolicyError {
+                kind: ReconnectPolicyErrorKind::MaxDelayTooShort,
+                inner: format!("{max_delay:?} is shorter than {initial_delay:?}").into(),
+            });
+        }
+
+        Ok(Self {
+            initial_delay,
+            max_delay,
+            jitter: true,
+        })
+    }
+
+    /// Sets whether to randomize each delay between half of it and all of it.
+    ///
+    /// Enabled by default.
+    #[must_use]
+    pub const fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the delay before reconnecting if the Mercure hub has not sent a
+    /// "retry" field.
+    pub const fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    /// Returns the maximum delay before reconnecting after consecutive
+    /// failures.
+    pub const fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    /// Returns whether each delay is randomized.
+    pub const fn has_jitter(&self) -> bool {
+        self.jitter
+    }
+
+    /// Returns the delay before reconnecting, given the "retry" field sent by
+    /// the Mercure hub and the number of consecutive failures to reconnect.
+    fn delay(&self, retry: Option<Duration>, failed_reconnects: u32) -> Duration {
+        let base = retry.unwrap_or(self.initial_delay).max(Self::MIN_DELAY);
+        let delay = base
+            .saturating_mul(2_u32.saturating_pow(failed_reconnects))
+            .min(self.max_delay.max(base));
+        if !self.jitter {
+            return delay;
+        }
+
+        let mut random = [0; 4];
+        if SystemRandom::new().fill(&mut random).is_err() {
+            return delay;
+        }
+        let fraction = f64::from(u32::from_le_bytes(random)) / f64::from(u32::MAX);
+        delay.mul_f64(0.5 + fraction / 2.0)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(3),
+            max_delay: Duration::from_secs(96),
+            jitter: true,
+        }
+    }
+}
+
+impl fmt::Display for ReconnectPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ReconnectPolicyErrorKind::InitialDelayTooShort => {
+                write!(
+                    f,
+                    "initial reconnection delay is too short: {err}",
+                    err = self.inner
+                )
+            },
+            ReconnectPolicyErrorKind::MaxDelayTooShort => {
+                write!(
+                    f,
+                    "maximum reconnection delay is shorter than the initial delay: {err}",
+                    err = self.inner
+                )
+            },
+        }
+    }
+}
+
+impl Error for ReconnectPolicyError {}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for ReconnectPolicyError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ReconnectPolicyError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl ReconnectPolicyError {
+    /// Returns the corresponding [`ReconnectPolicyErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &ReconnectPolicyErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            StreamErrorKind::ReadResponse => {
+                write!(
+                    f,
+                    "failed to read event stream from Mercure hub: {err}",
+                    err = self.inner
+                )
+            },
+            StreamErrorKind::ErrorStatus => {
+                write!(
+                    f,
+                    "Mercure hub responded to reconnection with an error status: {status}",
+                    status = self.inner
+                )
+            },
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            StreamErrorKind::ReadResponse => Some(self.inner.as_ref()),
+            StreamErrorKind::ErrorStatus => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for StreamError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("StreamError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl fmt::Display for DecodeUpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DecodeUpdateErrorKind::Stream => write!(f, "{err}", err = self.inner),
+            DecodeUpdateErrorKind::Decode => {
+                write!(f, "failed to decode update: {err}", err = self.inner)
+            },
+        }
+    }
+}
+
+impl Error for DecodeUpdateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.inner.as_ref())
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for DecodeUpdateError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("DecodeUpdateError", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl DecodeUpdateError {
+    /// Returns the corresponding [`DecodeUpdateErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &DecodeUpdateErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ParseSubscriptionEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse subscription event: {err}",
+            err = self.inner
+        )
+    }
+}
+
+impl Error for ParseSubscriptionEventError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.inner)
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl Serialize for ParseSubscriptionEventError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("ParseSubscriptionEventError", 1)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl StreamError {
+    /// Returns the corresponding [`StreamErrorKind`] for this error.
+    #[must_use]
+    pub const fn kind(&self) -> &StreamErrorKind {
+        &self.kind
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context as _, Result};
+
+    use super::*;
+
+    #[test]
+    fn it_validates_reconnect_policy() -> Result<()> {
+        let err = ReconnectPolicy::new(Duration::ZERO, Duration::from_secs(1))
+            .err()
+            .context("zero initial delay should be rejected")?;
+        assert!(matches!(
+            err.kind(),
+            ReconnectPolicyErrorKind::InitialDelayTooShort
+        ));
+        let err = ReconnectPolicy::new(Duration::from_secs(2), Duration::from_secs(1))
+            .err()
+            .context("maximum delay shorter than initial delay should be rejected")?;
+        assert!(matches!(
+            err.kind(),
+            ReconnectPolicyErrorKind::MaxDelayTooShort
+        ));
+        assert_eq!(
+            err.to_string(),
+            "maximum reconnection delay is shorter than the initial delay: 1s is shorter than 2s"
+        );
+
+        let reconnect_policy =
+            ReconnectPolicy::new(Duration::from_secs(1), Duration::from_secs(1))?;
+        assert!(reconnect_policy.has_jitter());
+        assert!(ReconnectPolicy::default().has_jitter());
+        Ok(())
+    }
+
+    #[test]
+    fn it_backs_off_reconnection_up_to_max_delay() -> Result<()> {
+        let reconnect_policy =
+            ReconnectPolicy::new(Duration::from_secs(1), Duration::from_secs(10))?.jitter(false);
+        assert_eq!(reconnect_policy.delay(None, 0), Duration::from_secs(1));
+        assert_eq!(reconnect_policy.delay(None, 3), Duration::from_secs(8));
+        assert_eq!(reconnect_policy.delay(None, 4), Duration::from_secs(10));
+        assert_eq!(
+            reconnect_policy.delay(None, u32::MAX),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            reconnect_policy.delay(Some(Duration::ZERO), 0),
+            ReconnectPolicy::MIN_DELAY
+        );
+        assert_eq!(
+            reconnect_policy.delay(Some(Duration::from_secs(20)), 1),
+            Duration::from_secs(20)
+        );
+
+        let reconnect_policy = reconnect_policy.jitter(true);
+        for _ in 0..100 {
+            let delay = reconnect_policy.delay(None, 3);
+            assert!(delay >= Duration::from_secs(4) && delay <= Duration::from_secs(8));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_subscription_event() -> Result<()> {
+        let update = Update {
+            id: Some("urn:uuid:1".to_owned()),
+            event_type: "message".to_owned(),
+            data: r#"{
+                "@context": "https://mercure.rocks/",
+                "id": "/.well-known/mercure/subscriptions/https%3A%2F%2Fexample.com%2Fbooks%2F1/urn%3Auuid%3A2",
+                "type": "Subscription",
+                "topic": "https://example.com/books/1",
+                "subscriber": "urn:uuid:2",
+                "active": true,
+                "payload": {"user": "https://example.com/users/1"}
+            }"#
+            .to_owned(),
+        };
+        let subscription_event = update.subscription_event()?;
+        assert_eq!(subscription_event.context(), Some("https://mercure.rocks/"));
+        assert_eq!(
+            subscription_event.id(),
+            "/.well-known/mercure/subscriptions/https%3A%2F%2Fexample.com%2Fbooks%2F1/urn%3Auuid%3A2"
+        );
+        assert_eq!(subscription_event.r#type(), "Subscription");
+        assert_eq!(subscription_event.topic(), "https://example.com/books/1");
+        assert_eq!(subscription_event.subscriber(), "urn:uuid:2");
+        assert!(subscription_event.is_active());
+        assert_eq!(
+            subscription_event.payload(),
+            Some(&serde_json::json!({"user": "https://example.com/users/1"}))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_subscription_event_without_optional_fields() -> Result<()> {
+        let update = Update {
+            id: None,
+            event_type: "message".to_owned(),
+            data: r#"{"id":"/.well-known/mercure/subscriptions/*/urn%3Auuid%3A2","type":"Subscription","topic":"*","subscriber":"urn:uuid:2","active":false}"#.to_owned(),
+        };
+        let subscription_event = update.subscription_event()?;
+        assert_eq!(subscription_event.context(), None);
+        assert!(!subscription_event.is_active());
+        assert_eq!(subscription_event.payload(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_to_parse_subscription_event_from_other_update() -> Result<()> {
+        let update = Update {
+            id: None,
+            event_type: "message".to_owned(),
+            data: r#"{"isbn":"9780735218789"}"#.to_owned(),
+        };
+        let err = update
+            .subscription_event()
+            .err()
+            .context("update should not be parsed as a subscription event")?;
+        assert!(err
+            .to_string()
+            .starts_with("failed to parse subscription event"));
+        Ok(())
+    }
+}
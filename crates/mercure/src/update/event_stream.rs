@@ -0,0 +1,240 @@
+//! A parser for the [`text/event-stream`] format.
+//!
+//! [`text/event-stream`]: https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation
+
+use std::collections::VecDeque;
+use std::mem;
+use std::time::Duration;
+
+/// An event dispatched from the event stream.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(super) struct Event {
+    pub(super) id: String,
+    pub(super) event_type: String,
+    pub(super) data: String,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct EventStreamParser {
+    line: Vec<u8>,
+    /// Whether the previous chunk ended with a CR, which may be followed by a
+    /// LF at the start of the next chunk.
+    after_cr: bool,
+    /// Whether the first line has been processed, i.e. any leading BOM has
+    /// been stripped.
+    started: bool,
+    event_type: String,
+    data: String,
+    last_event_id: String,
+    retry: Option<Duration>,
+    events: VecDeque<Event>,
+}
+
+impl EventStreamParser {
+    /// Feeds a chunk of the event stream to the parser.
+    pub(super) fn feed(&mut self, mut chunk: &[u8]) {
+        if self.after_cr {
+            self.after_cr = false;
+            if let Some(rest) = chunk.strip_prefix(b"\n") {
+                chunk = rest;
+            }
+        }
+
+        while let Some(pos) = chunk.iter().position(|&b| b == b'\r' || b == b'\n') {
+            let (line, rest) = chunk.split_at(pos);
+            self.line.extend_from_slice(line);
+            let line = mem::take(&mut self.line);
+            self.process_line(&String::from_utf8_lossy(&line));
+
+            chunk = match rest {
+                [b'\r', b'\n', rest @ ..] => rest,
+                [b'\r'] => {
+                    self.after_cr = true;
+                    &[]
+                },
+                [_, rest @ ..] => rest,
+                [] => unreachable!("`rest` should start with the line terminator"),
+            };
+        }
+        self.line.extend_from_slice(chunk);
+    }
+
+    /// Returns the next dispatched event, if any.
+    pub(super) fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Returns the reconnection time set by the last "retry" field, if any.
+    pub(super) fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Discards any incomplete event when the connection is closed.
+    ///
+    /// The last event ID and the reconnection time are kept, so that parsing
+    /// can continue with the event stream of a new connection.
+    ///
+    /// [HTML Standard, Section 9.2.6](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation)
+    ///
+    /// > Once the end of the file is reached, any pending data must be
+    /// > discarded. (If the file ends in the middle of an event, before the
+    /// > final empty line, the incomplete event is not dispatched.)
+    pub(super) fn discard_pending(&mut self) {
+        self.line.clear();
+        self.after_cr = false;
+        self.started = false;
+        self.event_type.clear();
+        self.data.clear();
+    }
+
+    fn process_line(&mut self, mut line: &str) {
+        if !self.started {
+            self.started = true;
+            line = line.strip_prefix('\u{FEFF}').unwrap_or(line);
+        }
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some(("", _)) => return,
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => {
+                self.event_type = value.to_owned();
+            },
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            },
+            "id" if !value.contains('\0') => {
+                self.last_event_id = value.to_owned();
+            },
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(millis) = value.parse() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn dispatch(&mut self) {
+        let event_type = mem::take(&mut self.event_type);
+        let mut data = mem::take(&mut self.data);
+        if data.is_empty() {
+            return;
+        }
+        data.pop();
+
+        self.events.push_back(Event {
+            id: self.last_event_id.clone(),
+            event_type: if event_type.is_empty() {
+                "message".to_owned()
+            } else {
+                event_type
+            },
+            data,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<Event> {
+        let mut parser = EventStreamParser::default();
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+        parser.discard_pending();
+        std::iter::from_fn(|| parser.next_event()).collect()
+    }
+
+    #[test]
+    fn it_concatenates_multi_line_data() -> Result<()> {
+        assert_eq!(
+            parse(&[
+                b"id: urn:uuid:1\nevent: BookUpdated\ndata: {\ndata:  \"isbn\": 1\ndata: }\n\n"
+            ]),
+            [Event {
+                id: "urn:uuid:1".to_owned(),
+                event_type: "BookUpdated".to_owned(),
+                data: "{\n \"isbn\": 1\n}".to_owned(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_ignores_comments_and_events_without_data() -> Result<()> {
+        assert_eq!(
+            parse(&[b": keep-alive\n\nevent: ping\n\ndata: 1\n\n"]),
+            [Event {
+                id: String::new(),
+                event_type: "message".to_owned(),
+                data: "1".to_owned(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_handles_line_terminators_split_across_chunks() -> Result<()> {
+        assert_eq!(
+            parse(&[
+                b"\xEF\xBB",
+                b"\xBFid: 1\r",
+                b"\ndata: a\r",
+                b"\r",
+                b"id: 2\rdata: b\r\r"
+            ]),
+            [
+                Event {
+                    id: "1".to_owned(),
+                    event_type: "message".to_owned(),
+                    data: "a".to_owned(),
+                },
+                Event {
+                    id: "2".to_owned(),
+                    event_type: "message".to_owned(),
+                    data: "b".to_owned(),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_discards_incomplete_event_at_end_of_stream() -> Result<()> {
+        assert_eq!(parse(&[b"data: 1\n\ndata: 2\n"]).len(), 1);
+        assert!(parse(&[b"data: 1"]).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_last_event_id() -> Result<()> {
+        let mut parser = EventStreamParser::default();
+        parser.feed(b"id: 1\ndata: a\n\ndata: b\n\nid\ndata: c\n\n");
+        assert_eq!(
+            parser.next_event().map(|event| event.id),
+            Some("1".to_owned())
+        );
+        assert_eq!(
+            parser.next_event().map(|event| event.id),
+            Some("1".to_owned())
+        );
+        assert_eq!(
+            parser.next_event().map(|event| event.id),
+            Some(String::new())
+        );
+        Ok(())
+    }
+}